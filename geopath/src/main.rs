@@ -4,16 +4,18 @@ mod options;
 
 use anyhow::Error as AnyError;
 use clap::Parser;
-use itertools::Itertools;
-use num_traits::{AsPrimitive, Float, FromPrimitive};
-use options::{Cli, Command as CliCmd};
-use propah::Point2Point;
+use num_traits::AsPrimitive;
+use options::{Cli, Command as CliCmd, QuantilesArgs};
+use propah::{
+    itm::{CurveAxis, ItmParams, Percentage},
+    Point2Point,
+};
 use rfprop::TerrainProfile as SigServeProfile;
 use serde::Serialize;
 use std::{io::Write, path::Path};
 use terrain::{
     geo::{coord, point, CoordFloat, Point},
-    TileMode, Tiles,
+    Obstructions, ProfileStats, TileMode, Tiles,
 };
 use textplots::{Chart, Plot, Shape};
 
@@ -25,7 +27,7 @@ fn main() -> Result<(), AnyError> {
         use_f32,
         max_step,
         earth_curve,
-        normalize,
+        normalize: _,
         start,
         dest,
         frequency,
@@ -39,9 +41,9 @@ fn main() -> Result<(), AnyError> {
     if use_f32 {
         type C = f32;
 
-        let terrain_profile: CommonProfile<C> = if rfprop {
+        let (terrain_profile, p2p): (CommonProfile<C>, _) = if rfprop {
             rfprop::init(Path::new(&tile_dir), false)?;
-            rfprop::terrain_profile(
+            let profile = rfprop::terrain_profile(
                 cli.start.0.y,
                 cli.start.0.x,
                 cli.start.1,
@@ -50,8 +52,8 @@ fn main() -> Result<(), AnyError> {
                 cli.dest.1,
                 900e6,
                 cli.normalize,
-            )
-            .into()
+            );
+            (profile.into(), None)
         } else {
             let start_point = coord!(x: start.0.x as C, y: start.0.y as C);
             let start_alt = start.1 as C;
@@ -64,7 +66,7 @@ fn main() -> Result<(), AnyError> {
             );
 
             let tile_src = Tiles::new(tile_dir, TileMode::MemMap)?;
-            Point2Point::<C>::builder()
+            let p2p = Point2Point::<C>::builder()
                 .freq(frequency as C)
                 .start(start_point)
                 .start_alt(start_alt)
@@ -72,9 +74,8 @@ fn main() -> Result<(), AnyError> {
                 .end(dest_point)
                 .end_alt(dest_alt)
                 .earth_curve(earth_curve)
-                .normalize(normalize)
-                .build(&tile_src)?
-                .into()
+                .build(&tile_src)?;
+            (p2p.clone().into(), Some(p2p))
         };
 
         match cmd {
@@ -82,13 +83,15 @@ fn main() -> Result<(), AnyError> {
             CliCmd::Plot => plot_ascii(&terrain_profile),
             CliCmd::Json => print_json(&terrain_profile)?,
             CliCmd::Tia => print_tia(&terrain_profile),
+            CliCmd::Stats => print_stats(&terrain_profile),
+            CliCmd::Quantiles(args) => print_quantiles(p2p.as_ref(), frequency, &args)?,
         };
     } else {
         type C = f64;
 
-        let terrain_profile: CommonProfile<C> = if rfprop {
+        let (terrain_profile, p2p): (CommonProfile<C>, _) = if rfprop {
             rfprop::init(Path::new(&tile_dir), false)?;
-            rfprop::terrain_profile(
+            let profile = rfprop::terrain_profile(
                 cli.start.0.y,
                 cli.start.0.x,
                 cli.start.1,
@@ -97,11 +100,11 @@ fn main() -> Result<(), AnyError> {
                 cli.dest.1,
                 frequency,
                 cli.normalize,
-            )
-            .into()
+            );
+            (profile.into(), None)
         } else {
             let tile_src = Tiles::new(tile_dir, TileMode::MemMap)?;
-            Point2Point::<C>::builder()
+            let p2p = Point2Point::<C>::builder()
                 .freq(frequency)
                 .start(coord!(x: start.0.x, y: start.0.y))
                 .start_alt(start.1)
@@ -109,9 +112,8 @@ fn main() -> Result<(), AnyError> {
                 .end(coord!(x: dest.0.x, y: dest.0.y))
                 .end_alt(dest.1)
                 .earth_curve(earth_curve)
-                .normalize(normalize)
-                .build(&tile_src)?
-                .into()
+                .build(&tile_src)?;
+            (p2p.clone().into(), Some(p2p))
         };
 
         match cmd {
@@ -119,6 +121,8 @@ fn main() -> Result<(), AnyError> {
             CliCmd::Plot => plot_ascii(&terrain_profile),
             CliCmd::Json => print_json(&terrain_profile)?,
             CliCmd::Tia => print_tia(&terrain_profile),
+            CliCmd::Stats => print_stats(&terrain_profile),
+            CliCmd::Quantiles(args) => print_quantiles(p2p.as_ref(), frequency, &args)?,
         };
     }
     Ok(())
@@ -127,7 +131,7 @@ fn main() -> Result<(), AnyError> {
 /// # Example with gnuplot
 ///
 /// ```sh
-/// cargo run -- --srtm-dir=data/nasadem/3arcsecond/ --max-step=90 --earth-curve --normalize --start=0,0,100 --dest=0,1,0 csv | tr ',' ' ' > ~/.tmp/plot && gnuplot -p -e "plot for [col=4:5] '~/.tmp/plot' using 1:col with lines"
+/// cargo run -- --srtm-dir=data/nasadem/3arcsecond/ --max-step=90 --earth-curve --start=0,0,100 --dest=0,1,0 csv | tr ',' ' ' > ~/.tmp/plot && gnuplot -p -e "plot for [col=4:5] '~/.tmp/plot' using 1:col with lines"
 /// ```
 fn print_csv<T: CoordFloat + std::fmt::Display>(
     profile: &CommonProfile<T>,
@@ -152,6 +156,54 @@ fn print_csv<T: CoordFloat + std::fmt::Display>(
     Ok(())
 }
 
+/// Prints ITM loss at each time percentage, with location fixed, and
+/// at each location percentage, with time fixed.
+fn print_quantiles<T>(
+    p2p: Option<&Point2Point<T>>,
+    frequency: f64,
+    args: &QuantilesArgs,
+) -> Result<(), AnyError>
+where
+    T: CoordFloat,
+    f64: From<T>,
+{
+    let p2p =
+        p2p.ok_or_else(|| anyhow::anyhow!("quantiles are not supported for rfprop profiles"))?;
+    let params = &ItmParams::builder()
+        .freq_hz(frequency)
+        .climate(args.climate.into())
+        .ground(args.ground.into())
+        .polarization(args.polarization.into())
+        .build()?;
+    let time: Percentage = args.time.try_into()?;
+    let location: Percentage = args.location.try_into()?;
+    let situation: Percentage = args.situation.try_into()?;
+
+    let time_curve = p2p.itm_curve(
+        params,
+        CurveAxis::Time {
+            location,
+            situation,
+        },
+    )?;
+    let location_curve = p2p.itm_curve(params, CurveAxis::Location { time, situation })?;
+    if !time_curve.warnings.is_empty() || !location_curve.warnings.is_empty() {
+        eprintln!(
+            "warnings: {:?}",
+            time_curve.warnings | location_curve.warnings
+        );
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "Percent,Time,Location")?;
+    for ((percent, time_db), (_, location_db)) in
+        time_curve.points.iter().zip(location_curve.points.iter())
+    {
+        writeln!(stdout, "{},{time_db},{location_db}", percent.get())?;
+    }
+    Ok(())
+}
+
 fn plot_ascii<T>(profile: &CommonProfile<T>)
 where
     T: CoordFloat + AsPrimitive<f32>,
@@ -194,37 +246,31 @@ where
 
 fn print_tia<T>(profile: &CommonProfile<T>)
 where
-    T: CoordFloat + FromPrimitive + std::fmt::Display + std::iter::Sum,
+    T: CoordFloat + std::fmt::Display,
 {
-    let tia = terrain_intersection_area(
+    let tia = Obstructions::new(
         &profile.distances_m,
-        &profile.los_elev_m,
         &profile.terrain_elev_m,
-    );
+        &profile.los_elev_m,
+    )
+    .intersection_area_m2;
     println!("{tia} m²");
 }
 
-/// Calculate the positive area of intersection, in m², between the
-/// profile (terrain) and the line of sight.
-fn terrain_intersection_area<T>(distances_m: &[T], los_elev_m: &[T], terrain_elev_m: &[T]) -> T
+fn print_stats<T>(profile: &CommonProfile<T>)
 where
-    T: Float + FromPrimitive + std::iter::Sum,
+    T: CoordFloat + std::fmt::Display,
 {
-    let tia_m2 = los_elev_m
-        .iter()
-        .zip(terrain_elev_m.iter())
-        .map(|(los, prof)| (*prof - *los).max(T::zero()))
-        .tuple_windows::<(T, T)>()
-        .zip(distances_m.iter().tuple_windows::<(&T, &T)>())
-        .map(|((h_n1, h_n), (d_n1, d_n))| {
-            let dx = (*d_n - *d_n1).abs();
-            dx * (h_n + h_n1) / T::from(2).unwrap()
-        })
-        .sum::<T>();
-
-    // Convert distance from `m^2` to `m*km` to stay compatible with
-    // DB assumptions.
-    tia_m2
+    let ProfileStats {
+        delta_h_m,
+        rms_roughness_m,
+        mean_slope,
+        flat_percent,
+    } = ProfileStats::new(&profile.distances_m, &profile.raw_terrain_elev_m);
+    println!("delta_h: {delta_h_m} m");
+    println!("rms_roughness: {rms_roughness_m} m");
+    println!("mean_slope: {mean_slope}");
+    println!("flat: {flat_percent}%");
 }
 
 /// A common represention of both native and rfprop profiles.
@@ -233,24 +279,31 @@ struct CommonProfile<T: CoordFloat> {
     distances_m: Box<[T]>,
     los_elev_m: Box<[T]>,
     terrain_elev_m: Box<[T]>,
+    /// Terrain without earth bulge, as statistics should describe
+    /// the ground itself.
+    raw_terrain_elev_m: Box<[T]>,
     fresnel_zone_m: Box<[T]>,
 }
 
 impl<T: CoordFloat> From<Point2Point<T>> for CommonProfile<T> {
-    fn from(
-        Point2Point {
+    fn from(p2p: Point2Point<T>) -> Self {
+        // Plots and TIA expect terrain raised by earth bulge under a
+        // straight LOS.
+        let terrain_elev_m = p2p.flat_earth_terrain_iter().collect();
+        let Point2Point {
             distances_m,
             great_circle,
             los_elev_m,
-            terrain_elev_m,
+            terrain_elev_m: raw_terrain_elev_m,
             lower_fresnel_zone_m: fresnel_zone_m,
-        }: Point2Point<T>,
-    ) -> Self {
+            ..
+        } = p2p;
         Self {
             great_circle,
             distances_m,
             los_elev_m,
             terrain_elev_m,
+            raw_terrain_elev_m,
             fresnel_zone_m,
         }
     }
@@ -268,7 +321,7 @@ impl From<SigServeProfile> for CommonProfile<f32> {
     ) -> Self {
         let distances_m = distance.iter().map(|val| *val as f32 * 1000.0).collect();
         let los_elev_m = los.iter().map(|val| *val as f32).collect();
-        let terrain_elev_m = los.iter().map(|val| *val as f32).collect();
+        let terrain_elev_m: Box<[f32]> = los.iter().map(|val| *val as f32).collect();
         let fresnel_zone_m = fresnel.iter().map(|val| *val as f32).collect();
         let great_circle = std::iter::repeat(point!(x: 0.0, y:0.0))
             .take(terrain.len())
@@ -277,6 +330,7 @@ impl From<SigServeProfile> for CommonProfile<f32> {
             great_circle,
             distances_m,
             los_elev_m,
+            raw_terrain_elev_m: terrain_elev_m.clone(),
             terrain_elev_m,
             fresnel_zone_m,
         }
@@ -297,11 +351,13 @@ impl From<SigServeProfile> for CommonProfile<f64> {
         let great_circle = std::iter::repeat(point!(x: 0.0, y:0.0))
             .take(terrain.len())
             .collect();
+        let terrain_elev_m: Box<[f64]> = terrain.into();
         Self {
             distances_m: distance.into(),
             great_circle,
             los_elev_m: los.into(),
-            terrain_elev_m: terrain.into(),
+            raw_terrain_elev_m: terrain_elev_m.clone(),
+            terrain_elev_m,
             fresnel_zone_m: fresnel.into(),
         }
     }
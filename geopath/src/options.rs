@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Error as AnyError};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use geo::geometry::Coord;
+use propah::itm::{Climate, Ground, Polarization};
 use std::{path::PathBuf, str::FromStr};
 
 /// Generate point-to-point terrain profiles.
@@ -26,8 +27,8 @@ pub struct Cli {
     pub earth_curve: bool,
 
     /// Center earth curve so that midpoint between start and end is
-    /// the highest.
-    #[arg(short, long, default_value_t = false)]
+    /// the highest (rfprop only).
+    #[arg(short, long, default_value_t = false, requires = "rfprop")]
     pub normalize: bool,
 
     /// Start "lat,lon,alt", where 'alt' is meters above ground.
@@ -83,4 +84,102 @@ pub enum Command {
 
     /// Calculate terrain itersection area in m²
     Tia,
+
+    /// Print terrain roughness statistics.
+    Stats,
+
+    /// Print ITM loss (dB) at time and location percentages 1
+    /// through 99 to stdout as CSV.
+    ///
+    /// ITM models earth curvature itself from the raw terrain, so
+    /// `--earth-curve` has no effect. Not supported with `--rfprop`.
+    Quantiles(QuantilesArgs),
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct QuantilesArgs {
+    /// Time percentage of the location curve.
+    #[arg(long, default_value_t = 50.0)]
+    pub time: f64,
+
+    /// Location percentage of the time curve.
+    #[arg(long, default_value_t = 50.0)]
+    pub location: f64,
+
+    /// Situation percentage of both curves.
+    #[arg(long, default_value_t = 50.0)]
+    pub situation: f64,
+
+    /// Radio climate.
+    #[arg(long, value_enum, default_value_t = ClimateArg::ContinentalTemperate)]
+    pub climate: ClimateArg,
+
+    /// Ground electrical properties.
+    #[arg(long, value_enum, default_value_t = GroundArg::Average)]
+    pub ground: GroundArg,
+
+    /// Antenna polarization.
+    #[arg(long, value_enum, default_value_t = PolarizationArg::Vertical)]
+    pub polarization: PolarizationArg,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ClimateArg {
+    Equatorial,
+    ContinentalSubtropical,
+    MaritimeSubtropical,
+    Desert,
+    ContinentalTemperate,
+    MaritimeTemperateOverLand,
+    MaritimeTemperateOverSea,
+}
+
+impl From<ClimateArg> for Climate {
+    fn from(arg: ClimateArg) -> Self {
+        match arg {
+            ClimateArg::Equatorial => Self::Equatorial,
+            ClimateArg::ContinentalSubtropical => Self::ContinentalSubtropical,
+            ClimateArg::MaritimeSubtropical => Self::MaritimeSubtropical,
+            ClimateArg::Desert => Self::Desert,
+            ClimateArg::ContinentalTemperate => Self::ContinentalTemperate,
+            ClimateArg::MaritimeTemperateOverLand => Self::MaritimeTemperateOverLand,
+            ClimateArg::MaritimeTemperateOverSea => Self::MaritimeTemperateOverSea,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GroundArg {
+    Poor,
+    Average,
+    Good,
+    FreshWater,
+    SeaWater,
+}
+
+impl From<GroundArg> for Ground {
+    fn from(arg: GroundArg) -> Self {
+        match arg {
+            GroundArg::Poor => Self::POOR,
+            GroundArg::Average => Self::AVERAGE,
+            GroundArg::Good => Self::GOOD,
+            GroundArg::FreshWater => Self::FRESH_WATER,
+            GroundArg::SeaWater => Self::SEA_WATER,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PolarizationArg {
+    Horizontal,
+    Vertical,
+}
+
+impl From<PolarizationArg> for Polarization {
+    fn from(arg: PolarizationArg) -> Self {
+        match arg {
+            PolarizationArg::Horizontal => Self::Horizontal,
+            PolarizationArg::Vertical => Self::Vertical,
+        }
+    }
 }
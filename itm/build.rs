@@ -28,12 +28,20 @@ fn main() {
     let mut bridge = cxx_build::bridge("src/lib.rs");
     bridge.flag("-std=c++11");
     bridge.include("../extern/itm/include");
-    #[cfg(feature = "address_sanitizer")]
+    // The two sanitizers can't be combined, e.g., with
+    // `--all-features`, so the thread sanitizer wins.
+    #[cfg(all(feature = "address_sanitizer", not(feature = "thread_sanitizer")))]
     {
         bridge.flag("-fno-omit-frame-pointer");
         bridge.flag("-fsanitize=address");
         bridge.flag("-ggdb");
     }
+    #[cfg(feature = "thread_sanitizer")]
+    {
+        bridge.flag("-fno-omit-frame-pointer");
+        bridge.flag("-fsanitize=thread");
+        bridge.flag("-ggdb");
+    }
     for path in &cxx_sources {
         bridge.file(path);
     }
@@ -2,7 +2,7 @@ mod options;
 
 use anyhow::Error as AnyErr;
 use clap::Parser;
-use itm::{Climate, ModeVariability, Polarization};
+use itm::{Climate, Ground, ItmParams};
 use options::{Cli, LatLonAlt};
 use terrain::{Profile, TileMode, Tiles};
 
@@ -30,45 +30,35 @@ fn main() -> Result<(), AnyErr> {
         .build(&tiles)?;
     let profile_runtime = t0.elapsed();
 
-    let climate = Climate::Desert;
-    let n0 = 301.;
-    let f_hz = frequency;
-    let pol = Polarization::Vertical;
-    let epsilon = 15.;
-    let sigma = 0.005;
-    let mdvar = ModeVariability::Accidental;
-    let time = 50.0;
-    let location = 50.0;
-    let situation = 50.0;
-    let step_size_m = profile.distances_m[1];
-    let terrain = profile.terrain_elev_m;
+    let params = ItmParams::builder()
+        .climate(Climate::Desert)
+        .freq_hz(frequency.into())
+        .ground(Ground::AVERAGE)
+        .build()?;
+    // Steps are uniform, so use the total distance rather than the
+    // first step, which accumulates rounding error over the path.
+    let total_distance_m = *profile.distances_m.last().unwrap();
+    #[allow(clippy::cast_precision_loss)]
+    let step_size_m = total_distance_m / (profile.distances_m.len() - 1) as f32;
     let t0 = std::time::Instant::now();
-    let attenuation_db = itm::p2p(
-        start_alt.into(),
-        end_alt.into(),
+    let (attenuation_db, warnings) = params.p2p(
+        profile.start_height.agl_m.into(),
+        profile.end_height.agl_m.into(),
         step_size_m.into(),
-        &terrain,
-        climate,
-        n0,
-        f_hz.into(),
-        pol,
-        epsilon,
-        sigma,
-        mdvar,
-        time,
-        location,
-        situation,
+        &profile.terrain_elev_m,
     )?;
     let itm_p2p_runtime = t0.elapsed();
 
-    let total_distance_m = profile.distances_m.last().unwrap();
-    let fspl = fspl(*total_distance_m, frequency);
+    let fspl = fspl(total_distance_m, frequency);
 
     println!("profile runtime: {profile_runtime:?}");
     println!("itm runtime:     {itm_p2p_runtime:?}");
     println!("distance:        {total_distance_m} m");
     println!("fspl:            {fspl} dB");
     println!("attenuation:     {attenuation_db} dB");
+    if !warnings.is_empty() {
+        println!("warnings:        {warnings:?}");
+    }
 
     Ok(())
 }
@@ -0,0 +1,539 @@
+//! Conformance tests against NTIA's example [inputs and outputs].
+//!
+//! Each `i_<name>.txt` input file is evaluated with the mode named by
+//! its prefix, and every result reported by the corresponding
+//! `o_<name>.txt` output file is checked: return code, warning flags,
+//! basic transmission loss and, when present, intermediate values.
+//! Point-to-point examples use the terrain in `pfl.txt`.
+//!
+//! [inputs and outputs]: https://github.com/NTIA/itm/tree/master/cmd_examples
+
+use crate::{
+    Climate, Ground, IntermediateValues, ItmErrCode, ItmParams, ItmParamsBuilder, ModeVariability,
+    Pfl, Polarization, SittingCriteria, Variability, Warnings,
+};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Examples in NTIA's repo; any others are reported by
+/// [`test_every_example_is_checked`].
+const EXAMPLES: &[&str] = &["p2ptls", "p2pcr", "areatls", "areacr"];
+
+fn cmd_examples_dir() -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "..",
+        "extern",
+        "itm",
+        "cmd_examples",
+    ]
+    .iter()
+    .collect()
+}
+
+/// Panics pointing at the likely cause of an unreadable example:
+/// a checkout without the ITM submodule.
+fn missing_example(path: &Path, err: &io::Error) -> ! {
+    panic!(
+        "can't read {}: {err}; fetch the ITM submodule with `git submodule update --init`",
+        path.display()
+    )
+}
+
+/// Returns the contents of one of NTIA's example files.
+fn cmd_example(name: &str) -> String {
+    let path = cmd_examples_dir().join(name);
+    fs::read_to_string(&path).unwrap_or_else(|err| missing_example(&path, &err))
+}
+
+/// Returns the `key,value` pairs of one of NTIA's example input
+/// files, with keys lowercased.
+fn cmd_example_inputs(name: &str) -> HashMap<String, Vec<f64>> {
+    cmd_example(name)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let key = fields.next()?.to_lowercase();
+            let values: Vec<f64> = fields.filter_map(|field| field.parse().ok()).collect();
+            (!values.is_empty()).then_some((key, values))
+        })
+        .collect()
+}
+
+/// Returns the example terrain profile, which is already in PFL
+/// format.
+fn cmd_example_pfl() -> Vec<f64> {
+    cmd_example("pfl.txt")
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|field| field.parse().ok())
+        .collect()
+}
+
+/// Returns the first value of the first of `keys` present in
+/// `inputs`.
+fn input(inputs: &HashMap<String, Vec<f64>>, keys: &[&str]) -> f64 {
+    keys.iter()
+        .find_map(|key| inputs.get(*key))
+        .unwrap_or_else(|| panic!("missing {keys:?}"))[0]
+}
+
+/// Returns the parameters of an example input file.
+fn example_params(inputs: &HashMap<String, Vec<f64>>) -> ItmParamsBuilder {
+    let variability = if inputs.contains_key("confidence") {
+        Variability::cr(
+            input(inputs, &["confidence"]),
+            input(inputs, &["reliability"]),
+        )
+    } else {
+        Variability::tls(
+            input(inputs, &["time"]),
+            input(inputs, &["location"]),
+            input(inputs, &["situation"]),
+        )
+    };
+    let builder = ItmParams::builder()
+        .climate(climate(input(inputs, &["climate"])))
+        .refractivity_n(input(inputs, &["n_0"]))
+        .freq_hz(input(inputs, &["f__mhz"]) * 1e6)
+        .polarization(polarization(input(inputs, &["pol"])))
+        .ground(Ground {
+            relative_permittivity: input(inputs, &["epsilon"]),
+            conductivity_s_per_m: input(inputs, &["sigma"]),
+        })
+        .variability(variability.unwrap());
+    mode_variability(builder, input(inputs, &["mdvar"]))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn siting(value: f64) -> SittingCriteria {
+    match value as i32 {
+        0 => SittingCriteria::Random,
+        1 => SittingCriteria::Careful,
+        2 => SittingCriteria::VeryCareful,
+        other => panic!("invalid siting criteria {other}"),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn climate(value: f64) -> Climate {
+    match value as i32 {
+        1 => Climate::Equatorial,
+        2 => Climate::ContinentalSubtropical,
+        3 => Climate::MaritimeSubtropical,
+        4 => Climate::Desert,
+        5 => Climate::ContinentalTemperate,
+        6 => Climate::MaritimeTemperateOverLand,
+        7 => Climate::MaritimeTemperateOverSea,
+        other => panic!("invalid climate {other}"),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn polarization(value: f64) -> Polarization {
+    match value as i32 {
+        0 => Polarization::Horizontal,
+        1 => Polarization::Vertical,
+        other => panic!("invalid polarization {other}"),
+    }
+}
+
+/// Applies an `mdvar` code: the mode of variability, plus 10 if
+/// location variability is eliminated and 20 if direct situation
+/// variability is.
+#[allow(clippy::cast_possible_truncation)]
+fn mode_variability(builder: ItmParamsBuilder, value: f64) -> ItmParamsBuilder {
+    let code = value as i32;
+    let mode = match code % 10 {
+        0 => ModeVariability::SingleMessage,
+        1 => ModeVariability::Accidental,
+        2 => ModeVariability::Mobile,
+        3 => ModeVariability::Broadcast,
+        _ => panic!("invalid mode of variability {code}"),
+    };
+    let (location, situation) = match code / 10 {
+        0 => (false, false),
+        1 => (true, false),
+        2 => (false, true),
+        3 => (true, true),
+        _ => panic!("invalid mode of variability {code}"),
+    };
+    builder
+        .mode_variability(mode)
+        .eliminate_location_variability(location)
+        .eliminate_situation_variability(situation)
+}
+
+/// A number as printed in an output file, which is only as precise
+/// as its printed digits.
+#[derive(Debug, Clone, Copy)]
+struct Printed {
+    value: f64,
+    tolerance: f64,
+}
+
+impl Printed {
+    fn parse(field: &str) -> Option<Self> {
+        let value: f64 = field.parse().ok()?;
+        let (mantissa, exponent) = match field.find(['e', 'E']) {
+            Some(idx) => (&field[..idx], field[idx + 1..].parse().ok()?),
+            None => (field, 0),
+        };
+        let decimals = mantissa.find('.').map_or(0, |idx| mantissa.len() - idx - 1);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let tolerance = 0.5 * 10_f64.powi(exponent - decimals as i32);
+        Some(Self { value, tolerance })
+    }
+
+    fn scaled(self, scale: f64) -> Self {
+        Self {
+            value: self.value * scale,
+            tolerance: self.tolerance * scale,
+        }
+    }
+
+    fn check(self, actual: f64, what: &str) {
+        assert!(
+            (actual - self.value).abs() <= self.tolerance * (1.0 + 1e-9),
+            "{what}: {actual}, expected {}",
+            self.value
+        );
+    }
+}
+
+/// One line of an output file: the label, the numbers following it
+/// and their unit, if any.
+struct OutputLine {
+    /// The label, with runs of whitespace collapsed.
+    label: String,
+    numbers: Vec<Printed>,
+    hex: Vec<i64>,
+    unit: Option<String>,
+}
+
+impl OutputLine {
+    fn parse(line: &str) -> Self {
+        let fields: Vec<&str> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        let label_len = fields
+            .iter()
+            .position(|field| {
+                Printed::parse(field).is_some()
+                    || field.starts_with("0x")
+                    || field.starts_with(['(', '['])
+            })
+            .unwrap_or(fields.len());
+        let label = fields[..label_len].join(" ");
+        let mut numbers = Vec::new();
+        let mut hex = Vec::new();
+        let mut unit = None;
+        for field in &fields[label_len..] {
+            if let Some(digits) = field.strip_prefix("0x") {
+                hex.extend(i64::from_str_radix(digits, 16).ok());
+            } else if let Some(printed) = Printed::parse(field) {
+                numbers.push(printed);
+            } else if let Some(inner) = field.strip_prefix('(') {
+                unit.get_or_insert_with(|| inner.trim_end_matches(')').to_lowercase());
+            } else if field.starts_with('[') {
+                // A description, e.g., "[No Warnings]".
+                break;
+            }
+        }
+        Self {
+            label,
+            numbers,
+            hex,
+            unit,
+        }
+    }
+
+    /// Returns the factor converting this line's numbers to SI, or
+    /// `default` if no unit is printed.
+    fn scale(&self, default: f64) -> f64 {
+        match self.unit.as_deref() {
+            Some("km") => 1e3,
+            Some("mrad") => 1e-3,
+            Some("deg" | "degrees") => 1_f64.to_radians(),
+            Some(_) => 1.0,
+            None => default,
+        }
+    }
+}
+
+/// Sections of an output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    /// Version and command line information.
+    Preamble,
+    Inputs,
+    Results,
+    IntermediateValues,
+}
+
+impl Section {
+    /// Returns the section headed by `label`, if any.
+    fn from_heading(label: &str) -> Option<Self> {
+        match label {
+            "Inputs" => Some(Self::Inputs),
+            "Results" => Some(Self::Results),
+            "Intermediate Values" => Some(Self::IntermediateValues),
+            _ => None,
+        }
+    }
+}
+
+/// An intermediate value reported by ITM, for each terminal when
+/// `paired`.
+struct Field {
+    /// Label, as named in ITM's `IntermediateValues` struct.
+    label: &'static str,
+    /// Factor converting the value to SI, unless the line prints its
+    /// unit.
+    scale: f64,
+    paired: bool,
+    get: fn(&IntermediateValues) -> [f64; 2],
+}
+
+/// Every intermediate value ITM reports.
+const FIELDS: &[Field] = &[
+    Field {
+        label: "theta_hzn",
+        scale: 1.0,
+        paired: true,
+        get: |values| values.horizon_angle_rad,
+    },
+    Field {
+        label: "d_hzn__meter",
+        scale: 1.0,
+        paired: true,
+        get: |values| values.horizon_distance_m,
+    },
+    Field {
+        label: "h_e__meter",
+        scale: 1.0,
+        paired: true,
+        get: |values| values.effective_height_m,
+    },
+    Field {
+        label: "N_s",
+        scale: 1.0,
+        paired: false,
+        get: |values| [values.surface_refractivity; 2],
+    },
+    Field {
+        label: "delta_h__meter",
+        scale: 1.0,
+        paired: false,
+        get: |values| [values.delta_h_m; 2],
+    },
+    Field {
+        label: "A_ref__db",
+        scale: 1.0,
+        paired: false,
+        get: |values| [values.reference_attenuation_db; 2],
+    },
+    Field {
+        label: "A_fs__db",
+        scale: 1.0,
+        paired: false,
+        get: |values| [values.free_space_loss_db; 2],
+    },
+    Field {
+        label: "d__km",
+        scale: 1e3,
+        paired: false,
+        get: |values| [values.distance_m; 2],
+    },
+    Field {
+        label: "mode",
+        scale: 1.0,
+        paired: false,
+        get: |values| [f64::from(values.mode as i32); 2],
+    },
+];
+
+/// Results reported by an output file.
+#[derive(Default)]
+struct Expected {
+    return_codes: Vec<i64>,
+    warnings: Vec<i64>,
+    losses: Vec<Printed>,
+    intermediate: Vec<(&'static Field, OutputLine)>,
+}
+
+impl Expected {
+    /// Parses an output file, panicking on any result or
+    /// intermediate value it doesn't recognize.
+    fn parse(name: &str) -> Self {
+        let mut expected = Self::default();
+        let mut section = Section::Preamble;
+        for line in cmd_example(name).lines().map(OutputLine::parse) {
+            if line.label.is_empty() && line.numbers.is_empty() && line.hex.is_empty() {
+                continue;
+            }
+            if let Some(heading) = Section::from_heading(&line.label) {
+                section = heading;
+                continue;
+            }
+            match (section, line.label.as_str()) {
+                (Section::Preamble | Section::Inputs, _) => {}
+                (Section::Results, "ITM Warning Flags") => expected.warnings.extend(&line.hex),
+                #[allow(clippy::cast_possible_truncation)]
+                (Section::Results, "ITM Return Code") => expected
+                    .return_codes
+                    .extend(line.numbers.iter().map(|code| code.value as i64)),
+                (Section::Results, "Basic Transmission Loss") => {
+                    expected.losses.extend(&line.numbers);
+                }
+                (Section::IntermediateValues, label) => {
+                    let field = FIELDS
+                        .iter()
+                        .find(|field| field.label == label)
+                        .unwrap_or_else(|| panic!("{name}: unknown intermediate value {label:?}"));
+                    expected.intermediate.push((field, line));
+                }
+                (Section::Results, label) => panic!("{name}: unknown result {label:?}"),
+            }
+        }
+        expected
+    }
+
+    /// Checks the results of evaluating each of an example's paths.
+    fn check(&self, name: &str, runs: &[Result<(f64, IntermediateValues), ItmErrCode>]) {
+        // A failing example reports its error code.
+        #[allow(clippy::cast_possible_truncation)]
+        if let Some(&code) = self.return_codes.iter().find(|&&code| code > 1) {
+            let expected = ItmErrCode::from_retcode(code as i32, ()).unwrap_err();
+            for run in runs {
+                let err = run.as_ref().expect_err(name);
+                assert_eq!(format!("{err:?}"), format!("{expected:?}"), "{name}");
+            }
+            return;
+        }
+        let runs: Vec<&(f64, IntermediateValues)> = runs
+            .iter()
+            .map(|run| run.as_ref().unwrap_or_else(|e| panic!("{name}: {e}")))
+            .collect();
+
+        assert_eq!(self.losses.len(), runs.len(), "{name}: losses");
+        for (i, (expected, (attenuation_db, _))) in self.losses.iter().zip(&runs).enumerate() {
+            expected.check(*attenuation_db, &format!("{name}: loss {i}"));
+        }
+
+        let warnings: Vec<Warnings> = runs.iter().map(|(_, values)| values.warnings).collect();
+        if self.warnings.len() == runs.len() {
+            for (expected, actual) in self.warnings.iter().zip(&warnings) {
+                assert_eq!(*actual, Warnings::from_raw(*expected), "{name}: warnings");
+            }
+        } else {
+            // Flags raised by any path.
+            let expected = self.warnings.iter().fold(0, |acc, bits| acc | bits);
+            let actual = warnings
+                .iter()
+                .fold(Warnings::empty(), |acc, warnings| acc | *warnings);
+            assert_eq!(actual, Warnings::from_raw(expected), "{name}: warnings");
+        }
+
+        for (field, line) in &self.intermediate {
+            let actual: Vec<f64> = runs
+                .iter()
+                .flat_map(|(_, values)| {
+                    let pair = (field.get)(values);
+                    if field.paired {
+                        pair.to_vec()
+                    } else {
+                        vec![pair[0]]
+                    }
+                })
+                .collect();
+            let what = format!("{name}: {}", field.label);
+            assert_eq!(line.numbers.len(), actual.len(), "{what}");
+            let scale = line.scale(field.scale);
+            for (expected, actual) in line.numbers.iter().zip(actual) {
+                expected.scaled(scale).check(actual, &what);
+            }
+        }
+    }
+}
+
+/// Evaluates the example `name` and checks its results.
+fn check_example(name: &str) {
+    let inputs = cmd_example_inputs(&format!("i_{name}.txt"));
+    let params = example_params(&inputs).build().unwrap();
+    let h_tx_m = input(&inputs, &["h_tx__meter"]);
+    let h_rx_m = input(&inputs, &["h_rx__meter"]);
+
+    let runs: Vec<_> = if name.starts_with("p2p") {
+        let pfl = cmd_example_pfl();
+        vec![params.p2p_pfl(h_tx_m, h_rx_m, Pfl::new(&pfl).unwrap())]
+    } else if name.starts_with("area") {
+        let tx_siting = siting(input(&inputs, &["tx_siting_criteria", "tx_site_criteria"]));
+        let rx_siting = siting(input(&inputs, &["rx_siting_criteria", "rx_site_criteria"]));
+        let delta_h_m = input(&inputs, &["delta_h__meter"]);
+        inputs["d__km"]
+            .iter()
+            .map(|d_km| params.area_ex(h_tx_m, h_rx_m, tx_siting, rx_siting, d_km * 1e3, delta_h_m))
+            .collect()
+    } else {
+        panic!("{name}: unknown mode");
+    };
+
+    Expected::parse(&format!("o_{name}.txt")).check(name, &runs);
+}
+
+#[test]
+fn test_pfl_example() {
+    let pfl = cmd_example_pfl();
+    let pfl = Pfl::new(&pfl).unwrap();
+    assert!((pfl.step_size_m() - 25.6).abs() < 1e-9);
+    let terrain: Vec<f64> = crate::fixtures::TERRAIN
+        .iter()
+        .copied()
+        .map(f64::from)
+        .collect();
+    assert_eq!(pfl.terrain(), terrain);
+}
+
+#[test]
+fn test_p2p_tls_example() {
+    check_example("p2ptls");
+}
+
+#[test]
+fn test_p2p_cr_example() {
+    check_example("p2pcr");
+}
+
+#[test]
+fn test_area_tls_example() {
+    check_example("areatls");
+}
+
+#[test]
+fn test_area_cr_example() {
+    check_example("areacr");
+}
+
+#[test]
+fn test_every_example_is_checked() {
+    let dir = cmd_examples_dir();
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap_or_else(|err| missing_example(&dir, &err))
+        .filter_map(|entry| {
+            let name = entry.unwrap().file_name().into_string().ok()?;
+            Some(name.strip_prefix("i_")?.strip_suffix(".txt")?.to_owned())
+        })
+        .collect();
+    names.sort();
+    for name in &names {
+        assert!(
+            EXAMPLES.contains(&name.as_str()),
+            "unchecked example {name}"
+        );
+    }
+    assert_eq!(names.len(), EXAMPLES.len());
+}
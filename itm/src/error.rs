@@ -2,9 +2,9 @@ use std::ffi::c_int;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ItmErrCode {
-    #[error("TX terminal height is out of range")]
+    #[error("TX terminal height is out of range (0.5..=3000 m)")]
     TxTerminalHeight,
-    #[error("RX terminal height is out of range")]
+    #[error("RX terminal height is out of range (0.5..=3000 m)")]
     RxTerminalHeight,
     #[error("Invalid value for radio climate")]
     InvalidRadioClimate,
@@ -18,15 +18,15 @@ pub enum ItmErrCode {
     InvalidConfidence,
     #[error("Reliability percentage is out of range")]
     InvalidReliability,
-    #[error("Refractivity is out of range")]
+    #[error("Refractivity is out of range (250..=400 N-Units)")]
     Refractivity,
-    #[error("Frequency is out of range")]
+    #[error("Frequency is out of range (20 MHz..=20 GHz)")]
     Frequency,
     #[error("Invalid value for polarization")]
     Polarization,
-    #[error("Epsilon is out of range")]
+    #[error("Epsilon is out of range (must be at least 1)")]
     Epsilon,
-    #[error("Sigma is out of range")]
+    #[error("Sigma is out of range (must be greater than 0)")]
     Sigma,
     #[error("The imaginary portion of the complex impedance is larger than the real portion")]
     GroundImpedance,
@@ -46,6 +46,14 @@ pub enum ItmErrCode {
     SurfaceRefractivitySmall,
     #[error("Internally computed surface refractivity value is too large")]
     SurfaceRefractivityLarge,
+    #[error("Percentage is out of range")]
+    InvalidPercentage,
+    #[error("missing required parameter '{0}'")]
+    Builder(&'static str),
+    /// A return code this crate doesn't know of, e.g., 1015, which
+    /// ITM leaves unused, or one added by a later version of ITM.
+    #[error("Unknown ITM return code {0}")]
+    Unknown(c_int),
 }
 
 impl ItmErrCode {
@@ -74,7 +82,7 @@ impl ItmErrCode {
             1020 => ItmErrCode::RxSitingCriteria,
             1021 => ItmErrCode::SurfaceRefractivitySmall,
             1022 => ItmErrCode::SurfaceRefractivityLarge,
-            _ => unreachable!(),
+            other => ItmErrCode::Unknown(other),
         };
         Err(err)
     }
@@ -0,0 +1,14 @@
+//! Data shared by unit and conformance tests.
+
+/// Terrain data taken from ITM's CLI example file <https://github.com/NTIA/itm/blob/master/cmd_examples/pfl.txt>
+pub(crate) const TERRAIN: &[u16] = &[
+    1692, 1692, 1693, 1693, 1693, 1693, 1693, 1693, 1694, 1694, 1694, 1694, 1694, 1694, 1694, 1694,
+    1694, 1695, 1695, 1695, 1695, 1695, 1695, 1695, 1695, 1696, 1696, 1696, 1696, 1696, 1696, 1697,
+    1697, 1697, 1697, 1697, 1697, 1697, 1697, 1697, 1697, 1698, 1698, 1698, 1698, 1698, 1698, 1698,
+    1698, 1698, 1698, 1699, 1699, 1699, 1699, 1699, 1699, 1700, 1700, 1700, 1700, 1700, 1700, 1700,
+    1701, 1701, 1701, 1701, 1701, 1701, 1702, 1702, 1702, 1702, 1702, 1702, 1702, 1702, 1703, 1703,
+    1703, 1703, 1703, 1703, 1703, 1703, 1703, 1704, 1704, 1704, 1704, 1704, 1704, 1704, 1704, 1705,
+    1705, 1705, 1705, 1705, 1705, 1705, 1705, 1705, 1705, 1706, 1706, 1706, 1706, 1706, 1706, 1706,
+    1706, 1706, 1707, 1707, 1707, 1707, 1707, 1707, 1707, 1708, 1708, 1708, 1708, 1708, 1708, 1708,
+    1708, 1709, 1709, 1709, 1709, 1709, 1710, 1710, 1710, 1710, 1710, 1710, 1710, 1710, 1709,
+];
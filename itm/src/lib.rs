@@ -1,23 +1,49 @@
+#[cfg(test)]
+mod conformance;
 mod error;
+#[cfg(test)]
+mod fixtures;
 mod params;
+mod pfl;
+mod results;
+mod validate;
+mod warnings;
 
 pub use crate::error::ItmErrCode;
-pub use params::{Climate, Mode, ModeVariability, Polarization, SittingCriteria};
+pub use params::{
+    Climate, CurveAxis, Ground, ItmParams, ItmParamsBuilder, Mode, ModeVariability, Percentage,
+    Polarization, SittingCriteria, Variability,
+};
+pub use pfl::Pfl;
+pub use results::{IntermediateValues, LossCurve};
+pub use warnings::Warnings;
+
+use rayon::prelude::*;
 
 #[cxx::bridge]
 mod ffi {
 
     #[derive(Default, Debug)]
-    struct P2PRes {
+    struct ItmRes {
         ret_code: i32,
         attenuation_db: f64,
+        warnings: i64,
+        theta_hzn: [f64; 2],
+        d_hzn_meter: [f64; 2],
+        h_e_meter: [f64; 2],
+        n_s: f64,
+        delta_h_meter: f64,
+        a_ref_db: f64,
+        a_fs_db: f64,
+        d_km: f64,
+        mode: i32,
     }
 
     unsafe extern "C++" {
         include!("itm/wrapper/itm-wrapper.h");
 
         #[allow(clippy::too_many_arguments)]
-        fn p2p(
+        fn p2p_tls_ex(
             h_tx_meter: f64,
             h_rx_meter: f64,
             pfl: &[f64],
@@ -31,151 +57,425 @@ mod ffi {
             time: f64,
             location: f64,
             situation: f64,
-        ) -> P2PRes;
+        ) -> ItmRes;
+
+        #[allow(clippy::too_many_arguments)]
+        fn p2p_cr_ex(
+            h_tx_meter: f64,
+            h_rx_meter: f64,
+            pfl: &[f64],
+            climate: i32,
+            N_0: f64,
+            f_mhz: f64,
+            pol: i32,
+            epsilon: f64,
+            sigma: f64,
+            mdvar: i32,
+            confidence: f64,
+            reliability: f64,
+        ) -> ItmRes;
+
+        #[allow(clippy::too_many_arguments)]
+        fn area_tls_ex(
+            h_tx_meter: f64,
+            h_rx_meter: f64,
+            tx_site_criteria: i32,
+            rx_site_criteria: i32,
+            d_km: f64,
+            delta_h_meter: f64,
+            climate: i32,
+            N_0: f64,
+            f_mhz: f64,
+            pol: i32,
+            epsilon: f64,
+            sigma: f64,
+            mdvar: i32,
+            time: f64,
+            location: f64,
+            situation: f64,
+        ) -> ItmRes;
+
+        #[allow(clippy::too_many_arguments)]
+        fn area_cr_ex(
+            h_tx_meter: f64,
+            h_rx_meter: f64,
+            tx_site_criteria: i32,
+            rx_site_criteria: i32,
+            d_km: f64,
+            delta_h_meter: f64,
+            climate: i32,
+            N_0: f64,
+            f_mhz: f64,
+            pol: i32,
+            epsilon: f64,
+            sigma: f64,
+            mdvar: i32,
+            confidence: f64,
+            reliability: f64,
+        ) -> ItmRes;
+
+        #[allow(clippy::too_many_arguments)]
+        fn variability(
+            time: f64,
+            location: f64,
+            situation: f64,
+            h_e_tx_meter: f64,
+            h_e_rx_meter: f64,
+            delta_h_meter: f64,
+            f_mhz: f64,
+            d_meter: f64,
+            A_ref_db: f64,
+            climate: i32,
+            mdvar: i32,
+            warnings: &mut i64,
+        ) -> f64;
     }
 }
 
-/// Returns the attenuation between two points.
-///
-/// See [C++] docs for info.
-///
-/// [C++]: https://github.com/dirkcgrunwald/itm/blob/31d068635380f61211e4ba43d50b03f0711b758e/src/itm_p2p.cpp#L5-L39
-///
-/// # Parameters
-///
-/// - `h_tx_meter`: transmiter height above ground (meters)
-/// - `h_rx_meter`: receiver height above ground (meters)
-/// - `step_size_m`: distance between each elevation sample (meters)
-/// - `terrain`: elevation samples spaced `step_size_m` apart from eachother (meters)
-/// - `climate`: see [`Climate`]
-/// - `n0`: refractivity (N-Units, where 301 = 4/3 earth radius)
-/// - `freq_hz`: frequency of signal (Hertz)
-/// - `pol`: see [`Polarization`]
-/// - `epsilon`: relative permittivity (Farads/meter)
-/// - `sigma`: ground conductivity (Siemens/meter)
-/// - `mdvar`: see [`ModeVariability`]
-/// - `time`: time percentage (0.0 < time < 100.0)
-/// - `location`: location percentage (0.0 < time < 100.0)
-/// - `situation`: situation percentage (0.0 < time < 100.0)
-///
-/// # Suggested Surface Paramters
-///
-/// | Ground attribute | Ground Conductivity | Relative ground Permittivity |
-/// |------------------|--------------------:|-----------------------------:|
-/// | Poor ground      |               0.001 |                            4 |
-/// | Average ground   |               0.005 |                           15 |
-/// | Good ground      |                0.02 |                           25 |
-/// | Fresh water      |                0.01 |                           25 |
-/// | Sea water        |                 5.0 |                           25 |
-///
-/// See [Radio Mobile] for source of this table.
-///
-/// [Radio Mobile]: http://radiomobile.pe1mew.nl/?Calculations___ITM_model_propagation_settings
-#[allow(clippy::too_many_arguments)]
-pub fn p2p<T>(
-    h_tx_meter: f64,
-    h_rx_meter: f64,
-    step_size_m: f64,
-    terrain: &[T],
-    climate: Climate,
-    n0: f64,
-    f_hz: f64,
-    pol: Polarization,
-    epsilon: f64,
-    sigma: f64,
-    mode_variability: ModeVariability,
-    time: f64,
-    location: f64,
-    situation: f64,
-) -> Result<f64, ItmErrCode>
-where
-    T: Copy,
-    f64: From<T>,
-{
-    let pfl = {
-        let mut pfl: Vec<f64> = Vec::with_capacity(terrain.len() + 2);
-        // Yes, we are pusing two additional non-elevation elemts into
-        // the vector, but we only need to compensate for 1.
+/// Model evaluation.
+impl ItmParams {
+    /// Returns the attenuation between two points, along with any
+    /// [`Warnings`] about its validity.
+    ///
+    /// See [C++] docs for info.
+    ///
+    /// [C++]: https://github.com/dirkcgrunwald/itm/blob/31d068635380f61211e4ba43d50b03f0711b758e/src/itm_p2p.cpp#L5-L39
+    ///
+    /// # Parameters
+    ///
+    /// - `h_tx_m`: transmiter height above ground (meters)
+    /// - `h_rx_m`: receiver height above ground (meters)
+    /// - `step_size_m`: distance between each elevation sample (meters)
+    /// - `terrain`: elevation samples spaced `step_size_m` apart from eachother (meters)
+    ///
+    /// Inputs are validated before calling into ITM, so out of range
+    /// heights or an empty `terrain` are reported as the
+    /// corresponding [`ItmErrCode`].
+    pub fn p2p<T>(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        step_size_m: f64,
+        terrain: &[T],
+    ) -> Result<(f64, Warnings), ItmErrCode>
+    where
+        T: Copy,
+        f64: From<T>,
+    {
+        self.p2p_ex(h_tx_m, h_rx_m, step_size_m, terrain)
+            .map(|(attenuation_db, values)| (attenuation_db, values.warnings))
+    }
+
+    /// Like [`ItmParams::p2p`], but also returns the
+    /// [`IntermediateValues`] the attenuation was derived from.
+    pub fn p2p_ex<T>(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        step_size_m: f64,
+        terrain: &[T],
+    ) -> Result<(f64, IntermediateValues), ItmErrCode>
+    where
+        T: Copy,
+        f64: From<T>,
+    {
+        validate::terminal_heights(h_tx_m, h_rx_m)?;
+        let mut buf = Vec::new();
+        let pfl = Pfl::write(&mut buf, step_size_m, terrain)?;
+        self.p2p_raw(h_tx_m, h_rx_m, pfl.as_slice(), self.variability)
+    }
+
+    /// Like [`ItmParams::p2p_ex`], but for a profile already in PFL
+    /// format.
+    pub fn p2p_pfl(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        pfl: Pfl<'_>,
+    ) -> Result<(f64, IntermediateValues), ItmErrCode> {
+        validate::terminal_heights(h_tx_m, h_rx_m)?;
+        self.p2p_raw(h_tx_m, h_rx_m, pfl.as_slice(), self.variability)
+    }
+
+    /// Evaluates `paths` in parallel.
+    ///
+    /// Returned results are in the same order as `paths`.
+    ///
+    /// Paths may share PFL buffers, as each call hands ITM a copy
+    /// owned by the calling worker, and `self` is shared between
+    /// workers. Running ITM on several threads at once relies on it
+    /// being reentrant, which `test_par_p2p_stress` checks against
+    /// serial runs, and exercises under ThreadSanitizer with the
+    /// `thread_sanitizer` feature.
+    pub fn par_p2p(
+        &self,
+        paths: &[P2pPath<'_>],
+    ) -> Vec<Result<(f64, IntermediateValues), ItmErrCode>> {
+        paths
+            .par_iter()
+            .map(|path| self.p2p_pfl(path.h_tx_m, path.h_rx_m, path.pfl))
+            .collect()
+    }
+
+    /// Returns the loss between two points at each percentage along
+    /// `axis`, overriding these parameters' variability.
+    ///
+    /// ITM is run once, at the median, for the path's geometry and
+    /// reference attenuation, which don't depend on variability.
+    /// Each percentage then only re-evaluates ITM's variability on
+    /// top of the free space loss, exactly as ITM itself does. See
+    /// [`ItmParams::p2p`] for a description of the remaining
+    /// parameters.
+    pub fn p2p_curve<T>(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        step_size_m: f64,
+        terrain: &[T],
+        axis: CurveAxis,
+    ) -> Result<LossCurve, ItmErrCode>
+    where
+        T: Copy,
+        f64: From<T>,
+    {
+        validate::terminal_heights(h_tx_m, h_rx_m)?;
+        let mut buf = Vec::new();
+        let pfl = Pfl::write(&mut buf, step_size_m, terrain)?;
+        // Unwrap is fine as 50 is within (0, 100).
+        let median = Percentage::new(50.0).unwrap();
+        let (time, location, situation) = axis.tls(median);
+        let (_, values) = self.p2p_raw(
+            h_tx_m,
+            h_rx_m,
+            pfl.as_slice(),
+            Variability::Tls {
+                time,
+                location,
+                situation,
+            },
+        )?;
+        // ITM's own path distance, rather than one rounded through
+        // `IntermediateValues::distance_m`.
         #[allow(clippy::cast_precision_loss)]
-        pfl.push((terrain.len() - 1) as f64);
-        pfl.push(step_size_m);
-        pfl.extend(terrain.iter().map(|elev| f64::from(*elev)));
-        pfl
-    };
+        let distance_m = (pfl.terrain().len() - 1) as f64 * pfl.step_size_m();
+        let mut warnings = i64::from(values.warnings.bits());
+        let points = LossCurve::PERCENTAGES
+            .map(|percent| {
+                // Unwrap is fine as every percentage is within (0, 100).
+                let percent = Percentage::new(<f64 as From<u8>>::from(percent)).unwrap();
+                let (time, location, situation) = axis.tls(percent);
+                let variability_db = ffi::variability(
+                    time.get(),
+                    location.get(),
+                    situation.get(),
+                    values.effective_height_m[0],
+                    values.effective_height_m[1],
+                    values.delta_h_m,
+                    self.freq_hz / 1e6,
+                    distance_m,
+                    values.reference_attenuation_db,
+                    self.climate as i32,
+                    self.mdvar(),
+                    &mut warnings,
+                );
+                (percent, variability_db + values.free_space_loss_db)
+            })
+            .collect();
+        Ok(LossCurve {
+            axis,
+            points,
+            warnings: Warnings::from_raw(warnings),
+        })
+    }
+
+    /// Returns the attenuation along `pfl`, a profile already in
+    /// ITM's format.
+    fn p2p_raw(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        pfl: &[f64],
+        variability: Variability,
+    ) -> Result<(f64, IntermediateValues), ItmErrCode> {
+        let res = match variability {
+            Variability::Tls {
+                time,
+                location,
+                situation,
+            } => ffi::p2p_tls_ex(
+                h_tx_m,
+                h_rx_m,
+                pfl,
+                self.climate as i32,
+                self.refractivity_n,
+                self.freq_hz / 1e6,
+                self.polarization as i32,
+                self.ground.relative_permittivity,
+                self.ground.conductivity_s_per_m,
+                self.mdvar(),
+                time.get(),
+                location.get(),
+                situation.get(),
+            ),
+            Variability::Cr {
+                confidence,
+                reliability,
+            } => ffi::p2p_cr_ex(
+                h_tx_m,
+                h_rx_m,
+                pfl,
+                self.climate as i32,
+                self.refractivity_n,
+                self.freq_hz / 1e6,
+                self.polarization as i32,
+                self.ground.relative_permittivity,
+                self.ground.conductivity_s_per_m,
+                self.mdvar(),
+                confidence.get(),
+                reliability.get(),
+            ),
+        };
+        ItmErrCode::from_retcode(
+            res.ret_code,
+            (res.attenuation_db, IntermediateValues::from(&res)),
+        )
+    }
 
-    let ffi::P2PRes {
-        ret_code,
-        attenuation_db,
-    } = ffi::p2p(
-        h_tx_meter,
-        h_rx_meter,
-        &pfl,
-        climate as i32,
-        n0,
-        f_hz / 1e6,
-        pol as i32,
-        epsilon,
-        sigma,
-        mode_variability as i32,
-        time,
-        location,
-        situation,
-    );
-    ItmErrCode::from_retcode(ret_code, attenuation_db)
+    /// Returns the attenuation over paths of `distance_m` through
+    /// terrain of irregularity `delta_h_m`, along with any
+    /// [`Warnings`] about its validity.
+    ///
+    /// Area mode is for when the terrain between the terminals is
+    /// not known, only its statistics. See [C++] docs for info.
+    ///
+    /// [C++]: https://github.com/dirkcgrunwald/itm/blob/31d068635380f61211e4ba43d50b03f0711b758e/src/itm_area.cpp
+    ///
+    /// # Parameters
+    ///
+    /// - `h_tx_m`: transmiter height above ground (meters)
+    /// - `h_rx_m`: receiver height above ground (meters)
+    /// - `tx_siting`: see [`SittingCriteria`]
+    /// - `rx_siting`: see [`SittingCriteria`]
+    /// - `distance_m`: path distance (meters)
+    /// - `delta_h_m`: terrain irregularity parameter (meters)
+    ///
+    /// Inputs are validated before calling into ITM, as with
+    /// [`ItmParams::p2p`].
+    pub fn area(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        tx_siting: SittingCriteria,
+        rx_siting: SittingCriteria,
+        distance_m: f64,
+        delta_h_m: f64,
+    ) -> Result<(f64, Warnings), ItmErrCode> {
+        self.area_ex(h_tx_m, h_rx_m, tx_siting, rx_siting, distance_m, delta_h_m)
+            .map(|(attenuation_db, values)| (attenuation_db, values.warnings))
+    }
+
+    /// Like [`ItmParams::area`], but also returns the
+    /// [`IntermediateValues`] the attenuation was derived from.
+    pub fn area_ex(
+        &self,
+        h_tx_m: f64,
+        h_rx_m: f64,
+        tx_siting: SittingCriteria,
+        rx_siting: SittingCriteria,
+        distance_m: f64,
+        delta_h_m: f64,
+    ) -> Result<(f64, IntermediateValues), ItmErrCode> {
+        validate::terminal_heights(h_tx_m, h_rx_m)?;
+        validate::area(distance_m, delta_h_m)?;
+        let res = match self.variability {
+            Variability::Tls {
+                time,
+                location,
+                situation,
+            } => ffi::area_tls_ex(
+                h_tx_m,
+                h_rx_m,
+                tx_siting as i32,
+                rx_siting as i32,
+                distance_m / 1e3,
+                delta_h_m,
+                self.climate as i32,
+                self.refractivity_n,
+                self.freq_hz / 1e6,
+                self.polarization as i32,
+                self.ground.relative_permittivity,
+                self.ground.conductivity_s_per_m,
+                self.mdvar(),
+                time.get(),
+                location.get(),
+                situation.get(),
+            ),
+            Variability::Cr {
+                confidence,
+                reliability,
+            } => ffi::area_cr_ex(
+                h_tx_m,
+                h_rx_m,
+                tx_siting as i32,
+                rx_siting as i32,
+                distance_m / 1e3,
+                delta_h_m,
+                self.climate as i32,
+                self.refractivity_n,
+                self.freq_hz / 1e6,
+                self.polarization as i32,
+                self.ground.relative_permittivity,
+                self.ground.conductivity_s_per_m,
+                self.mdvar(),
+                confidence.get(),
+                reliability.get(),
+            ),
+        };
+        ItmErrCode::from_retcode(
+            res.ret_code,
+            (res.attenuation_db, IntermediateValues::from(&res)),
+        )
+    }
+}
+
+/// One path of a [`ItmParams::par_p2p`] batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct P2pPath<'a> {
+    /// Transmitter height above ground (meters).
+    pub h_tx_m: f64,
+
+    /// Receiver height above ground (meters).
+    pub h_rx_m: f64,
+
+    /// Terrain between the terminals.
+    pub pfl: Pfl<'a>,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{p2p, Climate, ModeVariability, Polarization};
+    use super::{
+        Climate, CurveAxis, Ground, ItmErrCode, ItmParams, LossCurve, Mode, ModeVariability,
+        P2pPath, Percentage, Pfl, Polarization, SittingCriteria, Variability, Warnings,
+    };
+    use crate::fixtures::TERRAIN;
 
     #[test]
     fn test_p2p() {
-        // terrain data taken ITM's CLI example file <https://github.com/NTIA/itm/blob/master/cmd_examples/pfl.txt>
-        let terrain: &[u16] = &[
-            1692, 1692, 1693, 1693, 1693, 1693, 1693, 1693, 1694, 1694, 1694, 1694, 1694, 1694,
-            1694, 1694, 1694, 1695, 1695, 1695, 1695, 1695, 1695, 1695, 1695, 1696, 1696, 1696,
-            1696, 1696, 1696, 1697, 1697, 1697, 1697, 1697, 1697, 1697, 1697, 1697, 1697, 1698,
-            1698, 1698, 1698, 1698, 1698, 1698, 1698, 1698, 1698, 1699, 1699, 1699, 1699, 1699,
-            1699, 1700, 1700, 1700, 1700, 1700, 1700, 1700, 1701, 1701, 1701, 1701, 1701, 1701,
-            1702, 1702, 1702, 1702, 1702, 1702, 1702, 1702, 1703, 1703, 1703, 1703, 1703, 1703,
-            1703, 1703, 1703, 1704, 1704, 1704, 1704, 1704, 1704, 1704, 1704, 1705, 1705, 1705,
-            1705, 1705, 1705, 1705, 1705, 1705, 1705, 1706, 1706, 1706, 1706, 1706, 1706, 1706,
-            1706, 1706, 1707, 1707, 1707, 1707, 1707, 1707, 1707, 1708, 1708, 1708, 1708, 1708,
-            1708, 1708, 1708, 1709, 1709, 1709, 1709, 1709, 1710, 1710, 1710, 1710, 1710, 1710,
-            1710, 1710, 1709,
-        ];
-
         // Input: <https://github.com/NTIA/itm/blob/master/cmd_examples/i_p2ptls.txt>
-        let h_tx_meter = 15.;
-        let h_rx_meter = 3.;
-        let climate = Climate::ContinentalTemperate;
-        let n0 = 301.;
-        let f_hz = 3.5e9;
-        let pol = Polarization::Vertical;
-        let epsilon = 15.;
-        let sigma = 0.005;
-        let mdvar = ModeVariability::Accidental;
-        let time = 50.0;
-        let location = 50.0;
-        let situation = 50.0;
+        let h_tx_m = 15.;
+        let h_rx_m = 3.;
+        let params = ItmParams::builder()
+            .climate(Climate::ContinentalTemperate)
+            .refractivity_n(301.)
+            .freq_hz(3.5e9)
+            .polarization(Polarization::Vertical)
+            .ground(Ground::AVERAGE)
+            .mode_variability(ModeVariability::Accidental)
+            .variability(Variability::tls(50.0, 50.0, 50.0).unwrap())
+            .build()
+            .unwrap();
         let step_size_m = 25.6;
-        let attenuation_db = p2p(
-            h_tx_meter,
-            h_rx_meter,
-            step_size_m,
-            terrain,
-            climate,
-            n0,
-            f_hz,
-            pol,
-            epsilon,
-            sigma,
-            mdvar,
-            time,
-            location,
-            situation,
-        )
-        .unwrap();
+        let (attenuation_db, warnings) = params.p2p(h_tx_m, h_rx_m, step_size_m, TERRAIN).unwrap();
 
         // Output: <https://github.com/NTIA/itm/blob/master/cmd_examples/o_p2ptls.txt>
         // Results
@@ -183,5 +483,429 @@ mod tests {
         // ITM Return Code          0            [Success - No Errors]
         // Basic Transmission Loss  114.5        (dB)
         assert!((attenuation_db - 114.536_076_339_885_26).abs() < f64::EPSILON);
+        assert!(warnings.is_empty());
+
+        // The example is entirely the defaults.
+        assert_eq!(ItmParams::builder().freq_hz(3.5e9).build().unwrap(), params);
+    }
+
+    #[test]
+    fn test_variability() {
+        assert_eq!(Percentage::new(50.0).map(Percentage::get), Some(50.0));
+        for invalid in [0.0, 100.0, -1.0, f64::NAN] {
+            assert!(Percentage::new(invalid).is_none());
+        }
+        assert!(matches!(
+            Percentage::try_from(100.0),
+            Err(ItmErrCode::InvalidPercentage)
+        ));
+
+        assert!(matches!(
+            Variability::tls(50.0, 0.0, 50.0),
+            Err(ItmErrCode::InvalidLocation)
+        ));
+        assert!(matches!(
+            Variability::cr(50.0, 100.0),
+            Err(ItmErrCode::InvalidReliability)
+        ));
+        assert_eq!(
+            Variability::cr(90.0, 95.0).unwrap(),
+            Variability::Cr {
+                confidence: Percentage::new(90.0).unwrap(),
+                reliability: Percentage::new(95.0).unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        assert!(matches!(
+            ItmParams::builder().build(),
+            Err(ItmErrCode::Builder("freq_hz"))
+        ));
+        assert!(matches!(
+            ItmParams::builder().freq_hz(30e9).build(),
+            Err(ItmErrCode::Frequency)
+        ));
+        assert!(matches!(
+            ItmParams::builder()
+                .freq_hz(900e6)
+                .refractivity_n(200.)
+                .build(),
+            Err(ItmErrCode::Refractivity)
+        ));
+        // Permittivity and conductivity swapped.
+        assert!(matches!(
+            ItmParams::builder()
+                .freq_hz(900e6)
+                .ground(Ground {
+                    relative_permittivity: 0.005,
+                    conductivity_s_per_m: 15.0,
+                })
+                .build(),
+            Err(ItmErrCode::Epsilon)
+        ));
+        assert!(matches!(
+            ItmParams::builder()
+                .freq_hz(900e6)
+                .ground(Ground {
+                    relative_permittivity: 15.0,
+                    conductivity_s_per_m: 0.0,
+                })
+                .build(),
+            Err(ItmErrCode::Sigma)
+        ));
+
+        let params = ItmParams::builder()
+            .freq_hz(900e6)
+            .ground(Ground::SEA_WATER)
+            .build()
+            .unwrap();
+        assert_eq!(params.freq_hz(), 900e6);
+        assert_eq!(params.ground().conductivity_s_per_m, 5.0);
+        assert_eq!(params.climate(), Climate::ContinentalTemperate);
+        assert_eq!(params.mdvar(), 1);
+
+        // Eliminated variabilities offset ITM's mode of variability
+        // code.
+        let params = ItmParams::builder()
+            .freq_hz(900e6)
+            .mode_variability(ModeVariability::Broadcast)
+            .eliminate_location_variability(true)
+            .eliminate_situation_variability(true)
+            .build()
+            .unwrap();
+        assert_eq!(params.mdvar(), 33);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_params_serde() {
+        let params = ItmParams::builder()
+            .freq_hz(900e6)
+            .climate(Climate::Desert)
+            .ground(Ground::POOR)
+            .variability(Variability::cr(90.0, 95.0).unwrap())
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(serde_json::from_str::<ItmParams>(&json).unwrap(), params);
+
+        // Omitted parameters take their defaults, and are validated.
+        let minimal: ItmParams = serde_json::from_str(r#"{"freq_hz": 3.5e9}"#).unwrap();
+        assert_eq!(
+            minimal,
+            ItmParams::builder().freq_hz(3.5e9).build().unwrap()
+        );
+        assert!(serde_json::from_str::<ItmParams>(r#"{"freq_hz": 1.0}"#).is_err());
+        assert!(serde_json::from_str::<ItmParams>(
+            r#"{"freq_hz": 3.5e9, "variability": {"tls": {"time": 0, "location": 50, "situation": 50}}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_p2p_ex() {
+        // Input: <https://github.com/NTIA/itm/blob/master/cmd_examples/i_p2ptls.txt>
+        let params = ItmParams::builder().freq_hz(3.5e9).build().unwrap();
+        let run = || params.p2p_ex(15., 3., 25.6, TERRAIN).unwrap();
+        let (attenuation_db, values) = run();
+        assert!((attenuation_db - 114.536_076_339_885_26).abs() < f64::EPSILON);
+        assert_eq!(run(), (attenuation_db, values));
+
+        assert_ne!(values.mode, Mode::NotSet);
+        assert!(values.warnings.is_empty());
+        #[allow(clippy::cast_precision_loss)]
+        let distance_m = (TERRAIN.len() - 1) as f64 * 25.6;
+        assert!((values.distance_m - distance_m).abs() < 1e-6);
+        // ITM's free space loss, with frequency in MHz and distance in
+        // km.
+        let free_space_loss_db =
+            32.45 + 20.0 * 3500_f64.log10() + 20.0 * (distance_m / 1e3).log10();
+        assert!((values.free_space_loss_db - free_space_loss_db).abs() < 1e-6);
+        for i in 0..2 {
+            assert!(values.horizon_distance_m[i] > 0.0);
+            assert!(values.horizon_distance_m[i] <= distance_m);
+            assert!(values.effective_height_m[i] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_area_ex() {
+        let distance_m = 50e3;
+        let (attenuation_db, values) = ItmParams::builder()
+            .freq_hz(3.5e9)
+            .build()
+            .unwrap()
+            .area_ex(
+                15.,
+                3.,
+                SittingCriteria::Random,
+                SittingCriteria::Random,
+                distance_m,
+                90.,
+            )
+            .unwrap();
+        assert!((values.distance_m - distance_m).abs() < 1e-6);
+        assert!((values.delta_h_m - 90.0).abs() < 1e-6);
+        assert_ne!(values.mode, Mode::NotSet);
+        // Far beyond the horizon of such low terminals.
+        assert!(values.horizon_distance_m.iter().sum::<f64>() < distance_m);
+        assert!(attenuation_db > values.free_space_loss_db);
+    }
+
+    #[test]
+    fn test_warnings() {
+        // Above the recommended 10 GHz, but below the 20 GHz limit.
+        let (_, warnings) = ItmParams::builder()
+            .freq_hz(15e9)
+            .build()
+            .unwrap()
+            .p2p(15., 3., 25.6, TERRAIN)
+            .unwrap();
+        assert!(warnings.contains(Warnings::FREQUENCY));
+
+        assert_eq!(
+            Warnings::from_raw(0x0005),
+            Warnings::TX_TERMINAL_HEIGHT | Warnings::FREQUENCY
+        );
+        // Bits from future versions of ITM are kept.
+        assert_eq!(Warnings::from_raw(0x8000).bits(), 0x8000);
+    }
+
+    #[test]
+    fn test_p2p_curve() {
+        let params = ItmParams::builder().freq_hz(3.5e9).build().unwrap();
+        let median = Percentage::new(50.0).unwrap();
+        let time = params
+            .p2p_curve(
+                15.,
+                3.,
+                25.6,
+                TERRAIN,
+                CurveAxis::Time {
+                    location: median,
+                    situation: median,
+                },
+            )
+            .unwrap();
+        let location = params
+            .p2p_curve(
+                15.,
+                3.,
+                25.6,
+                TERRAIN,
+                CurveAxis::Location {
+                    time: median,
+                    situation: median,
+                },
+            )
+            .unwrap();
+
+        for curve in [&time, &location] {
+            assert_eq!(curve.points.len(), 99);
+            assert_eq!(curve.points[0].0.get(), 1.0);
+            assert_eq!(curve.points[98].0.get(), 99.0);
+            // Loss exceeded less often is higher.
+            assert!(curve.points.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            assert_eq!(curve.attenuation_db(0), None);
+        }
+
+        // Every point matches a full run of ITM at that percentage.
+        for curve in [&time, &location] {
+            for &(percent, attenuation_db) in &*curve.points {
+                let (time, location, situation) = curve.axis.tls(percent);
+                let params = ItmParams::builder()
+                    .freq_hz(3.5e9)
+                    .variability(Variability::Tls {
+                        time,
+                        location,
+                        situation,
+                    })
+                    .build()
+                    .unwrap();
+                let (expected_db, _) = params.p2p(15., 3., 25.6, TERRAIN).unwrap();
+                assert!(
+                    (attenuation_db - expected_db).abs() < 1e-9,
+                    "{percent:?}: {attenuation_db} != {expected_db}"
+                );
+            }
+        }
+
+        assert!(matches!(
+            params.p2p_curve(0.1, 3., 25.6, TERRAIN, time.axis),
+            Err(ItmErrCode::TxTerminalHeight)
+        ));
+        assert_eq!(LossCurve::PERCENTAGES.count(), 99);
+    }
+
+    /// Returns PFL buffers of every prefix, of at least two samples,
+    /// of [`TERRAIN`].
+    fn terrain_prefixes() -> Vec<Vec<f64>> {
+        (2..=TERRAIN.len())
+            .map(|len| {
+                let mut buf = Vec::new();
+                Pfl::write(&mut buf, 25.6, &TERRAIN[..len]).unwrap();
+                buf
+            })
+            .collect()
+    }
+
+    /// Returns a batch of paths cycling through `bufs` and a few
+    /// terminal heights.
+    fn batch(bufs: &[Vec<f64>], len: usize) -> Vec<P2pPath<'_>> {
+        (0..len)
+            .map(|i| P2pPath {
+                #[allow(clippy::cast_precision_loss)]
+                h_tx_m: 5.0 + (i % 7) as f64,
+                h_rx_m: 3.0,
+                pfl: Pfl::new(&bufs[i % bufs.len()]).unwrap(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pfl() {
+        let mut buf = vec![1.0; 1000];
+        let pfl = Pfl::write(&mut buf, 25.6, TERRAIN).unwrap();
+        assert_eq!(pfl.step_size_m(), 25.6);
+        assert_eq!(pfl.terrain().len(), TERRAIN.len());
+        assert_eq!(Pfl::new(pfl.as_slice()).unwrap(), pfl);
+        assert_eq!(buf.len(), TERRAIN.len() + 2);
+
+        let params = ItmParams::builder().freq_hz(3.5e9).build().unwrap();
+        assert_eq!(
+            params.p2p_pfl(15., 3., Pfl::new(&buf).unwrap()).unwrap(),
+            params.p2p_ex(15., 3., 25.6, TERRAIN).unwrap()
+        );
+
+        // Step count doesn't match the samples.
+        assert!(matches!(
+            Pfl::new(&[3.0, 25.6, 1.0, 2.0]),
+            Err(ItmErrCode::PathDistance)
+        ));
+        assert!(matches!(
+            Pfl::new(&[0.0, 25.6, 1.0]),
+            Err(ItmErrCode::PathDistance)
+        ));
+        assert!(matches!(Pfl::new(&[]), Err(ItmErrCode::PathDistance)));
+        assert!(matches!(
+            Pfl::new(&[1.0, -25.6, 1.0, 2.0]),
+            Err(ItmErrCode::PathDistance)
+        ));
+    }
+
+    #[test]
+    fn test_par_p2p() {
+        let params = ItmParams::builder().freq_hz(3.5e9).build().unwrap();
+        let bufs = terrain_prefixes();
+        let mut paths = batch(&bufs, 1000);
+        paths[10].h_tx_m = 0.0;
+
+        let results = params.par_p2p(&paths);
+        assert_eq!(results.len(), paths.len());
+        for (path, result) in paths.iter().zip(&results) {
+            let expected = params.p2p_pfl(path.h_tx_m, path.h_rx_m, path.pfl);
+            match (result, expected) {
+                (Ok(result), Ok(expected)) => assert_eq!(*result, expected),
+                (Err(ItmErrCode::TxTerminalHeight), Err(ItmErrCode::TxTerminalHeight)) => (),
+                other => panic!("{other:?}"),
+            }
+        }
+        assert!(matches!(results[10], Err(ItmErrCode::TxTerminalHeight)));
+    }
+
+    /// Hammers ITM from every worker, with buffers shared between
+    /// workers, and checks every result matches a serial run.
+    ///
+    /// Built with a sanitizer feature, the batch grows so the address
+    /// sanitizer can catch any unsafe memory access and the thread
+    /// sanitizer any data race, e.g.:
+    ///
+    /// ```text
+    /// RUSTFLAGS=-Zsanitizer=thread cargo +nightly test -Zbuild-std \
+    ///     --target x86_64-unknown-linux-gnu -p itm --features thread_sanitizer
+    /// ```
+    #[test]
+    fn test_par_p2p_stress() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ItmParams>();
+        assert_send_sync::<P2pPath<'_>>();
+
+        let params = ItmParams::builder().freq_hz(3.5e9).build().unwrap();
+        let bufs = terrain_prefixes();
+        let len = if cfg!(any(
+            feature = "address_sanitizer",
+            feature = "thread_sanitizer"
+        )) {
+            50_000
+        } else {
+            5_000
+        };
+        let paths = batch(&bufs, len);
+        let expected: Vec<_> = paths
+            .iter()
+            .map(|path| params.p2p_pfl(path.h_tx_m, path.h_rx_m, path.pfl).unwrap())
+            .collect();
+        for _ in 0..4 {
+            for (result, expected) in params.par_p2p(&paths).into_iter().zip(&expected) {
+                let (attenuation_db, values) = result.unwrap();
+                assert_eq!(attenuation_db.to_bits(), expected.0.to_bits());
+                assert_eq!(values, expected.1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        let params = ItmParams::builder().freq_hz(3.5e9).build().unwrap();
+        assert!(matches!(
+            params.p2p(0.1, 3., 25.6, TERRAIN),
+            Err(ItmErrCode::TxTerminalHeight)
+        ));
+        assert!(matches!(
+            params.p2p(15., f64::NAN, 25.6, TERRAIN),
+            Err(ItmErrCode::RxTerminalHeight)
+        ));
+        assert!(matches!(
+            params.p2p(15., 3., 25.6, &TERRAIN[..1]),
+            Err(ItmErrCode::PathDistance)
+        ));
+        assert!(matches!(
+            params.p2p::<f64>(15., 3., 25.6, &[]),
+            Err(ItmErrCode::PathDistance)
+        ));
+        assert!(matches!(
+            params.p2p(15., 3., 0.0, TERRAIN),
+            Err(ItmErrCode::PathDistance)
+        ));
+
+        let area = |distance_m, delta_h_m| {
+            params.area(
+                15.,
+                3.,
+                SittingCriteria::Random,
+                SittingCriteria::Random,
+                distance_m,
+                delta_h_m,
+            )
+        };
+        assert!(matches!(area(-1.0, 90.), Err(ItmErrCode::PathDistance)));
+        assert!(matches!(area(50e3, -1.0), Err(ItmErrCode::DeltaH)));
+
+        // Codes ITM doesn't define are errors rather than panics.
+        assert!(matches!(
+            ItmErrCode::from_retcode(1015, ()),
+            Err(ItmErrCode::Unknown(1015))
+        ));
+        assert!(matches!(
+            ItmErrCode::from_retcode(2000, ()),
+            Err(ItmErrCode::Unknown(2000))
+        ));
+        assert!(matches!(ItmErrCode::from_retcode(1, ()), Ok(())));
+        assert_eq!(
+            ItmErrCode::TxTerminalHeight.to_string(),
+            "TX terminal height is out of range (0.5..=3000 m)"
+        );
     }
 }
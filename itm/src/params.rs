@@ -1,3 +1,5 @@
+use crate::{validate, ItmErrCode};
+
 /// Antenna polarization.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
@@ -42,6 +44,18 @@ pub enum Mode {
     Troposcatter = 3,
 }
 
+impl Mode {
+    /// Returns the mode for ITM's numeric `code`.
+    pub(crate) fn from_code(code: i32) -> Self {
+        match code {
+            1 => Mode::LineOfSight,
+            2 => Mode::Diffraction,
+            3 => Mode::Troposcatter,
+            _ => Mode::NotSet,
+        }
+    }
+}
+
 /// Mode of variability.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
@@ -52,3 +66,389 @@ pub enum ModeVariability {
     Mobile = 2,
     Broadcast = 3,
 }
+
+/// A percentage strictly between 0 and 100, as required by ITM for
+/// all variability parameters.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "f64", into = "f64"))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Percentage(f64);
+
+impl Percentage {
+    /// Returns `percent` if it is within (0, 100).
+    pub fn new(percent: f64) -> Option<Self> {
+        (percent > 0.0 && percent < 100.0).then_some(Self(percent))
+    }
+
+    /// Returns the percentage as a number in (0, 100).
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for Percentage {
+    type Error = ItmErrCode;
+
+    fn try_from(percent: f64) -> Result<Self, Self::Error> {
+        Self::new(percent).ok_or(ItmErrCode::InvalidPercentage)
+    }
+}
+
+impl From<Percentage> for f64 {
+    fn from(percent: Percentage) -> f64 {
+        percent.0
+    }
+}
+
+/// How the statistical variability of a prediction is specified.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variability {
+    /// Time/location/situation.
+    Tls {
+        time: Percentage,
+        location: Percentage,
+        situation: Percentage,
+    },
+
+    /// Confidence/reliability, as broadcast and coverage
+    /// requirements are typically expressed.
+    Cr {
+        confidence: Percentage,
+        reliability: Percentage,
+    },
+}
+
+impl Variability {
+    /// Returns time/location/situation variability, or an error
+    /// naming the first percentage out of range.
+    pub fn tls(time: f64, location: f64, situation: f64) -> Result<Self, ItmErrCode> {
+        Ok(Self::Tls {
+            time: Percentage::new(time).ok_or(ItmErrCode::InvalidTime)?,
+            location: Percentage::new(location).ok_or(ItmErrCode::InvalidLocation)?,
+            situation: Percentage::new(situation).ok_or(ItmErrCode::InvalidSituation)?,
+        })
+    }
+
+    /// Returns confidence/reliability variability, or an error naming
+    /// the first percentage out of range.
+    pub fn cr(confidence: f64, reliability: f64) -> Result<Self, ItmErrCode> {
+        Ok(Self::Cr {
+            confidence: Percentage::new(confidence).ok_or(ItmErrCode::InvalidConfidence)?,
+            reliability: Percentage::new(reliability).ok_or(ItmErrCode::InvalidReliability)?,
+        })
+    }
+}
+
+/// Which percentage a [`LossCurve`](crate::LossCurve) sweeps, and the
+/// fixed values of the others.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveAxis {
+    /// Time percentage, at a fixed location and situation.
+    Time {
+        location: Percentage,
+        situation: Percentage,
+    },
+
+    /// Location percentage, at a fixed time and situation.
+    Location {
+        time: Percentage,
+        situation: Percentage,
+    },
+}
+
+impl CurveAxis {
+    /// Returns the time, location and situation at `percent` along
+    /// this axis.
+    pub(crate) fn tls(self, percent: Percentage) -> (Percentage, Percentage, Percentage) {
+        match self {
+            Self::Time {
+                location,
+                situation,
+            } => (percent, location, situation),
+            Self::Location { time, situation } => (time, percent, situation),
+        }
+    }
+}
+
+/// Electrical properties of the ground along a path.
+///
+/// # Presets
+///
+/// | Ground attribute | Ground Conductivity | Relative ground Permittivity |
+/// |------------------|--------------------:|-----------------------------:|
+/// | Poor ground      |               0.001 |                            4 |
+/// | Average ground   |               0.005 |                           15 |
+/// | Good ground      |                0.02 |                           25 |
+/// | Fresh water      |                0.01 |                           25 |
+/// | Sea water        |                 5.0 |                           25 |
+///
+/// See [Radio Mobile] for source of this table.
+///
+/// [Radio Mobile]: http://radiomobile.pe1mew.nl/?Calculations___ITM_model_propagation_settings
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ground {
+    /// Relative permittivity (epsilon).
+    pub relative_permittivity: f64,
+
+    /// Conductivity (sigma, Siemens/meter).
+    pub conductivity_s_per_m: f64,
+}
+
+impl Ground {
+    pub const POOR: Self = Self {
+        relative_permittivity: 4.0,
+        conductivity_s_per_m: 0.001,
+    };
+    pub const AVERAGE: Self = Self {
+        relative_permittivity: 15.0,
+        conductivity_s_per_m: 0.005,
+    };
+    pub const GOOD: Self = Self {
+        relative_permittivity: 25.0,
+        conductivity_s_per_m: 0.02,
+    };
+    pub const FRESH_WATER: Self = Self {
+        relative_permittivity: 25.0,
+        conductivity_s_per_m: 0.01,
+    };
+    pub const SEA_WATER: Self = Self {
+        relative_permittivity: 25.0,
+        conductivity_s_per_m: 5.0,
+    };
+}
+
+/// Radio and environmental parameters shared by every path of a
+/// link configuration.
+///
+/// Use [`ItmParams::builder`] to construct, which validates each
+/// parameter's range.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ItmParamsBuilder"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItmParams {
+    pub(crate) climate: Climate,
+    pub(crate) refractivity_n: f64,
+    pub(crate) freq_hz: f64,
+    pub(crate) polarization: Polarization,
+    pub(crate) ground: Ground,
+    pub(crate) mode_variability: ModeVariability,
+    pub(crate) eliminate_location_variability: bool,
+    pub(crate) eliminate_situation_variability: bool,
+    pub(crate) variability: Variability,
+}
+
+impl ItmParams {
+    /// Returns a builder with the defaults listed on each of its
+    /// setters.
+    pub fn builder() -> ItmParamsBuilder {
+        ItmParamsBuilder::default()
+    }
+
+    /// Returns the radio climate.
+    pub fn climate(&self) -> Climate {
+        self.climate
+    }
+
+    /// Returns the surface refractivity (N-Units).
+    pub fn refractivity_n(&self) -> f64 {
+        self.refractivity_n
+    }
+
+    /// Returns the signal frequency (Hertz).
+    pub fn freq_hz(&self) -> f64 {
+        self.freq_hz
+    }
+
+    /// Returns the antenna polarization.
+    pub fn polarization(&self) -> Polarization {
+        self.polarization
+    }
+
+    /// Returns the ground's electrical properties.
+    pub fn ground(&self) -> Ground {
+        self.ground
+    }
+
+    /// Returns the mode of variability.
+    pub fn mode_variability(&self) -> ModeVariability {
+        self.mode_variability
+    }
+
+    /// Returns whether location variability is eliminated.
+    pub fn eliminate_location_variability(&self) -> bool {
+        self.eliminate_location_variability
+    }
+
+    /// Returns whether direct situation variability is eliminated.
+    pub fn eliminate_situation_variability(&self) -> bool {
+        self.eliminate_situation_variability
+    }
+
+    /// Returns the variability percentages.
+    pub fn variability(&self) -> Variability {
+        self.variability
+    }
+
+    /// Returns ITM's `mdvar` code: the mode of variability, plus 10
+    /// if location variability is eliminated and 20 if direct
+    /// situation variability is.
+    pub(crate) fn mdvar(&self) -> i32 {
+        self.mode_variability as i32
+            + 10 * i32::from(self.eliminate_location_variability)
+            + 20 * i32::from(self.eliminate_situation_variability)
+    }
+}
+
+/// Builds [`ItmParams`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItmParamsBuilder {
+    climate: Climate,
+    refractivity_n: f64,
+    freq_hz: Option<f64>,
+    polarization: Polarization,
+    ground: Ground,
+    mode_variability: ModeVariability,
+    eliminate_location_variability: bool,
+    eliminate_situation_variability: bool,
+    variability: Variability,
+}
+
+impl Default for ItmParamsBuilder {
+    fn default() -> Self {
+        Self {
+            climate: Climate::ContinentalTemperate,
+            refractivity_n: 301.0,
+            freq_hz: None,
+            polarization: Polarization::Vertical,
+            ground: Ground::AVERAGE,
+            mode_variability: ModeVariability::Accidental,
+            eliminate_location_variability: false,
+            eliminate_situation_variability: false,
+            variability: Variability::Tls {
+                time: Percentage(50.0),
+                location: Percentage(50.0),
+                situation: Percentage(50.0),
+            },
+        }
+    }
+}
+
+impl ItmParamsBuilder {
+    /// Radio climate.
+    ///
+    /// Default: [`Climate::ContinentalTemperate`].
+    #[must_use]
+    pub fn climate(mut self, climate: Climate) -> Self {
+        self.climate = climate;
+        self
+    }
+
+    /// Surface refractivity (N-Units, 250..=400).
+    ///
+    /// Default: 301, which corresponds to a 4/3 earth radius.
+    #[must_use]
+    pub fn refractivity_n(mut self, n_units: f64) -> Self {
+        self.refractivity_n = n_units;
+        self
+    }
+
+    /// Signal frequency (Hertz, 20 MHz..=20 GHz).
+    ///
+    /// Required.
+    #[must_use]
+    pub fn freq_hz(mut self, hz: f64) -> Self {
+        self.freq_hz = Some(hz);
+        self
+    }
+
+    /// Antenna polarization.
+    ///
+    /// Default: [`Polarization::Vertical`].
+    #[must_use]
+    pub fn polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+
+    /// Ground electrical properties, e.g., one of the [`Ground`]
+    /// presets.
+    ///
+    /// Default: [`Ground::AVERAGE`].
+    #[must_use]
+    pub fn ground(mut self, ground: Ground) -> Self {
+        self.ground = ground;
+        self
+    }
+
+    /// Mode of variability.
+    ///
+    /// Default: [`ModeVariability::Accidental`].
+    #[must_use]
+    pub fn mode_variability(mut self, mode_variability: ModeVariability) -> Self {
+        self.mode_variability = mode_variability;
+        self
+    }
+
+    /// Eliminates location variability, e.g., when predicting the
+    /// loss of a single, known, path.
+    ///
+    /// Default: `false`.
+    #[must_use]
+    pub fn eliminate_location_variability(mut self, eliminate: bool) -> Self {
+        self.eliminate_location_variability = eliminate;
+        self
+    }
+
+    /// Eliminates direct situation variability.
+    ///
+    /// Default: `false`.
+    #[must_use]
+    pub fn eliminate_situation_variability(mut self, eliminate: bool) -> Self {
+        self.eliminate_situation_variability = eliminate;
+        self
+    }
+
+    /// Time/location/situation or confidence/reliability
+    /// percentages.
+    ///
+    /// Default: 50% time, location and situation.
+    #[must_use]
+    pub fn variability(mut self, variability: Variability) -> Self {
+        self.variability = variability;
+        self
+    }
+
+    /// Validates and returns the parameters.
+    pub fn build(&self) -> Result<ItmParams, ItmErrCode> {
+        let freq_hz = self.freq_hz.ok_or(ItmErrCode::Builder("freq_hz"))?;
+        validate::frequency(freq_hz)?;
+        validate::refractivity(self.refractivity_n)?;
+        validate::ground(self.ground)?;
+        Ok(ItmParams {
+            climate: self.climate,
+            refractivity_n: self.refractivity_n,
+            freq_hz,
+            polarization: self.polarization,
+            ground: self.ground,
+            mode_variability: self.mode_variability,
+            eliminate_location_variability: self.eliminate_location_variability,
+            eliminate_situation_variability: self.eliminate_situation_variability,
+            variability: self.variability,
+        })
+    }
+}
+
+impl TryFrom<ItmParamsBuilder> for ItmParams {
+    type Error = ItmErrCode;
+
+    fn try_from(builder: ItmParamsBuilder) -> Result<Self, Self::Error> {
+        builder.build()
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{validate, ItmErrCode};
+
+/// A borrowed terrain profile in ITM's PFL format.
+///
+/// The format is the number of steps, the distance between samples
+/// (meters), and then the elevation samples (meters). Keeping
+/// profiles in this format, e.g., in one reusable buffer per worker,
+/// avoids converting and allocating on every call to ITM.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pfl<'a>(&'a [f64]);
+
+impl<'a> Pfl<'a> {
+    /// Wraps `pfl`, a profile already in PFL format.
+    ///
+    /// Returns [`ItmErrCode::PathDistance`] if the number of steps
+    /// doesn't match the number of elevation samples, or the path
+    /// doesn't have a positive length.
+    pub fn new(pfl: &'a [f64]) -> Result<Self, ItmErrCode> {
+        let (&steps, &step_size_m) = match pfl {
+            [steps, step_size_m, ..] => (steps, step_size_m),
+            _ => return Err(ItmErrCode::PathDistance),
+        };
+        let samples = pfl.len() - 2;
+        #[allow(clippy::cast_precision_loss)]
+        let expected_steps = samples.saturating_sub(1) as f64;
+        if steps != expected_steps {
+            return Err(ItmErrCode::PathDistance);
+        }
+        validate::profile(step_size_m, samples)?;
+        Ok(Self(pfl))
+    }
+
+    /// Writes `terrain`, elevation samples `step_size_m` apart, to
+    /// `buf` in PFL format.
+    ///
+    /// `buf` is cleared first, and its allocation is reused.
+    pub fn write<T>(
+        buf: &'a mut Vec<f64>,
+        step_size_m: f64,
+        terrain: &[T],
+    ) -> Result<Self, ItmErrCode>
+    where
+        T: Copy,
+        f64: From<T>,
+    {
+        validate::profile(step_size_m, terrain.len())?;
+        buf.clear();
+        buf.reserve(terrain.len() + 2);
+        // Yes, we are pusing two additional non-elevation elemts into
+        // the vector, but we only need to compensate for 1.
+        #[allow(clippy::cast_precision_loss)]
+        buf.push((terrain.len() - 1) as f64);
+        buf.push(step_size_m);
+        buf.extend(terrain.iter().map(|elev| f64::from(*elev)));
+        Ok(Self(buf))
+    }
+
+    /// Returns the distance between elevation samples (meters).
+    pub fn step_size_m(self) -> f64 {
+        self.0[1]
+    }
+
+    /// Returns the elevation samples (meters).
+    pub fn terrain(self) -> &'a [f64] {
+        &self.0[2..]
+    }
+
+    /// Returns the profile in PFL format.
+    pub fn as_slice(self) -> &'a [f64] {
+        self.0
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{ffi, CurveAxis, Mode, Percentage, Warnings};
+
+/// Intermediate values computed by ITM on the way to a prediction.
+///
+/// These explain a prediction, e.g., whether a path was treated as
+/// line of sight or diffracted, and which terminal's horizon limited
+/// it. Pairs are ordered `[tx, rx]`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntermediateValues {
+    /// Propagation mode.
+    pub mode: Mode,
+
+    /// Horizon elevation angles (radians).
+    pub horizon_angle_rad: [f64; 2],
+
+    /// Distances to each terminal's horizon (meters).
+    pub horizon_distance_m: [f64; 2],
+
+    /// Effective terminal heights (meters).
+    pub effective_height_m: [f64; 2],
+
+    /// Terrain irregularity parameter (meters).
+    pub delta_h_m: f64,
+
+    /// Surface refractivity (N-Units).
+    pub surface_refractivity: f64,
+
+    /// Path distance (meters).
+    pub distance_m: f64,
+
+    /// Free space basic transmission loss (dB).
+    pub free_space_loss_db: f64,
+
+    /// Reference attenuation, relative to free space (dB).
+    pub reference_attenuation_db: f64,
+
+    /// Warnings about the validity of the prediction.
+    pub warnings: Warnings,
+}
+
+impl From<&ffi::ItmRes> for IntermediateValues {
+    fn from(res: &ffi::ItmRes) -> Self {
+        Self {
+            mode: Mode::from_code(res.mode),
+            horizon_angle_rad: res.theta_hzn,
+            horizon_distance_m: res.d_hzn_meter,
+            effective_height_m: res.h_e_meter,
+            delta_h_m: res.delta_h_meter,
+            surface_refractivity: res.n_s,
+            distance_m: res.d_km * 1e3,
+            free_space_loss_db: res.a_fs_db,
+            reference_attenuation_db: res.a_ref_db,
+            warnings: Warnings::from_raw(res.warnings),
+        }
+    }
+}
+
+/// Loss at each whole percentage from 1 to 99 along one
+/// [`CurveAxis`], e.g., for fade margin planning.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LossCurve {
+    /// The swept percentage and the fixed values of the others.
+    pub axis: CurveAxis,
+
+    /// `(percentage, attenuation_db)` in ascending order of
+    /// percentage.
+    pub points: Box<[(Percentage, f64)]>,
+
+    /// Warnings raised at any point of the curve.
+    pub warnings: Warnings,
+}
+
+impl LossCurve {
+    /// Percentages evaluated along the axis.
+    pub const PERCENTAGES: std::ops::RangeInclusive<u8> = 1..=99;
+
+    /// Returns the loss at `percent`, if it is one of
+    /// [`PERCENTAGES`](Self::PERCENTAGES).
+    pub fn attenuation_db(&self, percent: u8) -> Option<f64> {
+        self.points
+            .iter()
+            .find(|(p, _)| p.get() == f64::from(percent))
+            .map(|&(_, attenuation_db)| attenuation_db)
+    }
+}
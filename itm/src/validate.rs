@@ -0,0 +1,69 @@
+//! Input validation, mirroring ITM's [ValidateInputs.cpp].
+//!
+//! Inputs are checked here so out of range values are reported
+//! before calling into ITM. Each check returns the same error ITM
+//! would for that input.
+//!
+//! [ValidateInputs.cpp]: https://github.com/NTIA/itm/blob/master/src/ValidateInputs.cpp
+
+use crate::{Ground, ItmErrCode};
+
+/// Terminal heights above ground (meters).
+pub(crate) fn terminal_heights(h_tx_m: f64, h_rx_m: f64) -> Result<(), ItmErrCode> {
+    const RANGE_M: std::ops::RangeInclusive<f64> = 0.5..=3000.0;
+    if !RANGE_M.contains(&h_tx_m) {
+        return Err(ItmErrCode::TxTerminalHeight);
+    }
+    if !RANGE_M.contains(&h_rx_m) {
+        return Err(ItmErrCode::RxTerminalHeight);
+    }
+    Ok(())
+}
+
+/// Surface refractivity (N-Units).
+pub(crate) fn refractivity(n_units: f64) -> Result<(), ItmErrCode> {
+    if !(250.0..=400.0).contains(&n_units) {
+        return Err(ItmErrCode::Refractivity);
+    }
+    Ok(())
+}
+
+/// Signal frequency (Hertz).
+pub(crate) fn frequency(hz: f64) -> Result<(), ItmErrCode> {
+    if !(20e6..=20e9).contains(&hz) {
+        return Err(ItmErrCode::Frequency);
+    }
+    Ok(())
+}
+
+/// Ground electrical properties.
+pub(crate) fn ground(ground: Ground) -> Result<(), ItmErrCode> {
+    if !(1.0..=f64::INFINITY).contains(&ground.relative_permittivity) {
+        return Err(ItmErrCode::Epsilon);
+    }
+    if ground.conductivity_s_per_m.is_nan() || ground.conductivity_s_per_m <= 0.0 {
+        return Err(ItmErrCode::Sigma);
+    }
+    Ok(())
+}
+
+/// Point-to-point terrain profile.
+///
+/// ITM requires at least two samples a positive distance apart.
+pub(crate) fn profile(step_size_m: f64, samples: usize) -> Result<(), ItmErrCode> {
+    if samples < 2 || !step_size_m.is_finite() || step_size_m <= 0.0 {
+        return Err(ItmErrCode::PathDistance);
+    }
+    Ok(())
+}
+
+/// Area mode path distance and terrain irregularity (meters).
+pub(crate) fn area(distance_m: f64, delta_h_m: f64) -> Result<(), ItmErrCode> {
+    if !distance_m.is_finite() || distance_m <= 0.0 {
+        return Err(ItmErrCode::PathDistance);
+    }
+    if !(0.0..=f64::INFINITY).contains(&delta_h_m) {
+        return Err(ItmErrCode::DeltaH);
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+bitflags::bitflags! {
+    /// Warnings ITM raises for inputs it accepts, but for which its
+    /// predictions are of doubtful validity.
+    ///
+    /// See NTIA's [docs] for details.
+    ///
+    /// [docs]: https://github.com/NTIA/itm#warning-flags
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Warnings: u32 {
+        /// TX terminal height is near its limits.
+        const TX_TERMINAL_HEIGHT = 0x0001;
+        /// RX terminal height is near its limits.
+        const RX_TERMINAL_HEIGHT = 0x0002;
+        /// Frequency is outside the recommended range.
+        const FREQUENCY = 0x0004;
+        /// Path distance is near its upper limit.
+        const PATH_DISTANCE_TOO_BIG_1 = 0x0008;
+        /// Path distance is large; care must be taken with the result.
+        const PATH_DISTANCE_TOO_BIG_2 = 0x0010;
+        /// Path distance is near its lower limit.
+        const PATH_DISTANCE_TOO_SMALL_1 = 0x0020;
+        /// Path distance is small; care must be taken with the result.
+        const PATH_DISTANCE_TOO_SMALL_2 = 0x0040;
+        /// TX horizon angle is large, so small angle approximations
+        /// could break down.
+        const TX_HORIZON_ANGLE = 0x0080;
+        /// RX horizon angle is large, so small angle approximations
+        /// could break down.
+        const RX_HORIZON_ANGLE = 0x0100;
+        /// TX horizon distance is less than 1/10 of the smooth earth
+        /// horizon distance.
+        const TX_HORIZON_DISTANCE_1 = 0x0200;
+        /// RX horizon distance is less than 1/10 of the smooth earth
+        /// horizon distance.
+        const RX_HORIZON_DISTANCE_1 = 0x0400;
+        /// TX horizon distance is greater than 3 times the smooth
+        /// earth horizon distance.
+        const TX_HORIZON_DISTANCE_2 = 0x0800;
+        /// RX horizon distance is greater than 3 times the smooth
+        /// earth horizon distance.
+        const RX_HORIZON_DISTANCE_2 = 0x1000;
+        /// One of the variabilities is far in the tail of its
+        /// distribution.
+        const EXTREME_VARIABILITIES = 0x2000;
+        /// Internally computed surface refractivity is small; care
+        /// must be taken with the result.
+        const SURFACE_REFRACTIVITY = 0x4000;
+    }
+}
+
+impl Warnings {
+    /// Returns the flags of ITM's raw `warnings` output, retaining
+    /// any bits not known to this crate.
+    pub(crate) fn from_raw(warnings: i64) -> Self {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Self::from_bits_retain(warnings as u32)
+    }
+}
@@ -3,6 +3,45 @@
 #include "itm.h"
 #include "itm/src/lib.rs.h"
 
+#include <vector>
+
+/*
+ * ITM takes the PFL as a mutable pointer even though it only reads
+ * it. The slice is borrowed from Rust as shared, possibly by several
+ * workers at once, so writing through a cast-away const would be
+ * undefined behaviour, and nothing in ITM's interface stops a future
+ * version of the submodule from doing so. Hand ITM a copy in a buffer
+ * owned by the calling thread instead. Its allocation is reused, so
+ * this costs one memcpy per call, which is small next to ITM itself.
+ */
+static double *pfl_copy(rust::Slice<const double> pfl) {
+    static thread_local std::vector<double> buf;
+    buf.assign(pfl.data(), pfl.data() + pfl.size());
+    return buf.data();
+}
+
+/*=============================================================================
+ |
+ |  Description: Copies ITM's outputs into the result shared with Rust.
+ |
+ *===========================================================================*/
+static void copy_intermediate_values(long warnings,
+                                     const IntermediateValues &inter_values,
+                                     ItmRes &res) {
+    res.warnings = warnings;
+    for (int i = 0; i < 2; i++) {
+        res.theta_hzn[i] = inter_values.theta_hzn[i];
+        res.d_hzn_meter[i] = inter_values.d_hzn__meter[i];
+        res.h_e_meter[i] = inter_values.h_e__meter[i];
+    }
+    res.n_s = inter_values.N_s;
+    res.delta_h_meter = inter_values.delta_h__meter;
+    res.a_ref_db = inter_values.A_ref__db;
+    res.a_fs_db = inter_values.A_fs__db;
+    res.d_km = inter_values.d__km;
+    res.mode = inter_values.mode;
+}
+
 /*=============================================================================
  |
  |  Description: The ITS Irregular Terrain Model (ITM).  This function
@@ -34,39 +73,281 @@
  |
  |      Outputs:  A__db             - Basic transmission loss, in dB
  |                warnings          - Warning flags
+ |                interValues       - Struct of intermediate values
  |
  |      Returns:  error             - Error code
  |
  *===========================================================================*/
-P2PRes p2p(double h_tx__meter,
-           double h_rx__meter,
-           rust::Slice<const double> pfl,
-           int climate,
-           double N_0,
-           double f__mhz,
-           int pol,
-           double epsilon,
-           double sigma,
-           int mdvar,
-           double time,
-           double location,
-           double situation) {
-    P2PRes res;
-    long warnings = -1;
-    res.ret_code = ITM_P2P_TLS(h_tx__meter,
-                               h_rx__meter,
-                               const_cast<double *>(pfl.data()),
-                               climate,
-                               N_0,
-                               f__mhz,
-                               pol,
-                               epsilon,
-                               sigma,
-                               mdvar,
-                               time,
+ItmRes p2p_tls_ex(double h_tx__meter,
+                  double h_rx__meter,
+                  rust::Slice<const double> pfl,
+                  int climate,
+                  double N_0,
+                  double f__mhz,
+                  int pol,
+                  double epsilon,
+                  double sigma,
+                  int mdvar,
+                  double time,
+                  double location,
+                  double situation) {
+    ItmRes res;
+    long warnings = 0;
+    IntermediateValues inter_values{};
+    res.ret_code = ITM_P2P_TLS_Ex(h_tx__meter,
+                                  h_rx__meter,
+                                  pfl_copy(pfl),
+                                  climate,
+                                  N_0,
+                                  f__mhz,
+                                  pol,
+                                  epsilon,
+                                  sigma,
+                                  mdvar,
+                                  time,
+                                  location,
+                                  situation,
+                                  &res.attenuation_db,
+                                  &warnings,
+                                  &inter_values);
+    copy_intermediate_values(warnings, inter_values, res);
+    return res;
+}
+
+/*=============================================================================
+ |
+ |  Description: The ITS Irregular Terrain Model (ITM).  This function
+ |               exposes point-to-point mode functionality, with variability
+ |               specified with confidence/reliability (CR).
+ |
+ |        Input:  Same as p2p_tls_ex, except:
+ |
+ |                confidence        - Confidence percentage, 0 < confidence < 100
+ |                reliability       - Reliability percentage, 0 < reliability < 100
+ |
+ |      Outputs:  A__db             - Basic transmission loss, in dB
+ |                warnings          - Warning flags
+ |                interValues       - Struct of intermediate values
+ |
+ |      Returns:  error             - Error code
+ |
+ *===========================================================================*/
+ItmRes p2p_cr_ex(double h_tx__meter,
+                 double h_rx__meter,
+                 rust::Slice<const double> pfl,
+                 int climate,
+                 double N_0,
+                 double f__mhz,
+                 int pol,
+                 double epsilon,
+                 double sigma,
+                 int mdvar,
+                 double confidence,
+                 double reliability) {
+    ItmRes res;
+    long warnings = 0;
+    IntermediateValues inter_values{};
+    res.ret_code = ITM_P2P_CR_Ex(h_tx__meter,
+                                 h_rx__meter,
+                                 pfl_copy(pfl),
+                                 climate,
+                                 N_0,
+                                 f__mhz,
+                                 pol,
+                                 epsilon,
+                                 sigma,
+                                 mdvar,
+                                 confidence,
+                                 reliability,
+                                 &res.attenuation_db,
+                                 &warnings,
+                                 &inter_values);
+    copy_intermediate_values(warnings, inter_values, res);
+    return res;
+}
+
+/*=============================================================================
+ |
+ |  Description: The ITS Irregular Terrain Model (ITM).  This function
+ |               exposes area mode functionality, with variability
+ |               specified with time/location/situation (TLS).
+ |
+ |        Input:  h_tx__meter       - Structural height of the TX, in meters
+ |                h_rx__meter       - Structural height of the RX, in meters
+ |                tx_site_criteria  - Siting criteria of the TX
+ |                                      + 0 : SITING_CRITERIA__RANDOM
+ |                                      + 1 : SITING_CRITERIA__CAREFUL
+ |                                      + 2 : SITING_CRITERIA__VERY_CAREFUL
+ |                rx_site_criteria  - Siting criteria of the RX
+ |                d__km             - Path distance, in km
+ |                delta_h__meter    - Terrain irregularity parameter
+ |                climate           - Radio climate
+ |                N_0               - Refractivity, in N-Units
+ |                f__mhz            - Frequency, in MHz
+ |                pol               - Polarization
+ |                epsilon           - Relative permittivity
+ |                sigma             - Conductivity
+ |                mdvar             - Mode of variability
+ |                time              - Time percentage, 0 < time < 100
+ |                location          - Location percentage, 0 < location < 100
+ |                situation         - Situation percentage, 0 < situation < 100
+ |
+ |      Outputs:  A__db             - Basic transmission loss, in dB
+ |                warnings          - Warning flags
+ |                interValues       - Struct of intermediate values
+ |
+ |      Returns:  error             - Error code
+ |
+ *===========================================================================*/
+ItmRes area_tls_ex(double h_tx__meter,
+                   double h_rx__meter,
+                   int tx_site_criteria,
+                   int rx_site_criteria,
+                   double d__km,
+                   double delta_h__meter,
+                   int climate,
+                   double N_0,
+                   double f__mhz,
+                   int pol,
+                   double epsilon,
+                   double sigma,
+                   int mdvar,
+                   double time,
+                   double location,
+                   double situation) {
+    ItmRes res;
+    long warnings = 0;
+    IntermediateValues inter_values{};
+    res.ret_code = ITM_AREA_TLS_Ex(h_tx__meter,
+                                   h_rx__meter,
+                                   tx_site_criteria,
+                                   rx_site_criteria,
+                                   d__km,
+                                   delta_h__meter,
+                                   climate,
+                                   N_0,
+                                   f__mhz,
+                                   pol,
+                                   epsilon,
+                                   sigma,
+                                   mdvar,
+                                   time,
+                                   location,
+                                   situation,
+                                   &res.attenuation_db,
+                                   &warnings,
+                                   &inter_values);
+    copy_intermediate_values(warnings, inter_values, res);
+    return res;
+}
+
+/*=============================================================================
+ |
+ |  Description: The ITS Irregular Terrain Model (ITM).  This function
+ |               exposes area mode functionality, with variability
+ |               specified with confidence/reliability (CR).
+ |
+ |        Input:  Same as area_tls_ex, except:
+ |
+ |                confidence        - Confidence percentage, 0 < confidence < 100
+ |                reliability       - Reliability percentage, 0 < reliability < 100
+ |
+ |      Outputs:  A__db             - Basic transmission loss, in dB
+ |                warnings          - Warning flags
+ |                interValues       - Struct of intermediate values
+ |
+ |      Returns:  error             - Error code
+ |
+ *===========================================================================*/
+ItmRes area_cr_ex(double h_tx__meter,
+                  double h_rx__meter,
+                  int tx_site_criteria,
+                  int rx_site_criteria,
+                  double d__km,
+                  double delta_h__meter,
+                  int climate,
+                  double N_0,
+                  double f__mhz,
+                  int pol,
+                  double epsilon,
+                  double sigma,
+                  int mdvar,
+                  double confidence,
+                  double reliability) {
+    ItmRes res;
+    long warnings = 0;
+    IntermediateValues inter_values{};
+    res.ret_code = ITM_AREA_CR_Ex(h_tx__meter,
+                                  h_rx__meter,
+                                  tx_site_criteria,
+                                  rx_site_criteria,
+                                  d__km,
+                                  delta_h__meter,
+                                  climate,
+                                  N_0,
+                                  f__mhz,
+                                  pol,
+                                  epsilon,
+                                  sigma,
+                                  mdvar,
+                                  confidence,
+                                  reliability,
+                                  &res.attenuation_db,
+                                  &warnings,
+                                  &inter_values);
+    copy_intermediate_values(warnings, inter_values, res);
+    return res;
+}
+
+/*=============================================================================
+ |
+ |  Description: ITM's variability, for re-evaluating a point-to-point
+ |               or area prediction at other time/location/situation
+ |               percentages without recomputing its reference
+ |               attenuation.
+ |
+ |        Input:  time              - Time percentage, 0 < time < 100
+ |                location          - Location percentage, 0 < location < 100
+ |                situation         - Situation percentage, 0 < situation < 100
+ |                h_e_tx__meter     - Effective height of the TX, in meters
+ |                h_e_rx__meter     - Effective height of the RX, in meters
+ |                delta_h__meter    - Terrain irregularity parameter
+ |                f__mhz            - Frequency, in MHz
+ |                d__meter          - Path distance, in meters
+ |                A_ref__db         - Reference attenuation, in dB
+ |                climate           - Radio climate
+ |                mdvar             - Mode of variability
+ |
+ |      Outputs:  warnings          - Warning flags, added to those passed in
+ |
+ |      Returns:  Loss relative to free space, in dB
+ |
+ *===========================================================================*/
+double variability(double time,
+                   double location,
+                   double situation,
+                   double h_e_tx__meter,
+                   double h_e_rx__meter,
+                   double delta_h__meter,
+                   double f__mhz,
+                   double d__meter,
+                   double A_ref__db,
+                   int climate,
+                   int mdvar,
+                   int64_t &warnings) {
+    double h_e__meter[2] = {h_e_tx__meter, h_e_rx__meter};
+    long flags = warnings;
+    double A__db = Variability(time,
                                location,
                                situation,
-                               &res.attenuation_db,
-                               &warnings);
-    return res;
+                               h_e__meter,
+                               delta_h__meter,
+                               f__mhz,
+                               d__meter,
+                               A_ref__db,
+                               climate,
+                               mdvar,
+                               &flags);
+    warnings = flags;
+    return A__db;
 }
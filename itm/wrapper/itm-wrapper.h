@@ -2,20 +2,79 @@
 
 #include "rust/cxx.h"
 
-struct P2PRes;
+struct ItmRes;
 
 int poc(rust::slice<const double> terrain);
 
-P2PRes p2p(double h_tx__meter,
-           double h_rx__meter,
-           rust::Slice<const double> pfl,
-           int climate,
-           double N_0,
-           double f__mhz,
-           int pol,
-           double epsilon,
-           double sigma,
-           int mdvar,
-           double time,
-           double location,
-           double situation);
+ItmRes p2p_tls_ex(double h_tx__meter,
+                  double h_rx__meter,
+                  rust::Slice<const double> pfl,
+                  int climate,
+                  double N_0,
+                  double f__mhz,
+                  int pol,
+                  double epsilon,
+                  double sigma,
+                  int mdvar,
+                  double time,
+                  double location,
+                  double situation);
+
+ItmRes p2p_cr_ex(double h_tx__meter,
+                 double h_rx__meter,
+                 rust::Slice<const double> pfl,
+                 int climate,
+                 double N_0,
+                 double f__mhz,
+                 int pol,
+                 double epsilon,
+                 double sigma,
+                 int mdvar,
+                 double confidence,
+                 double reliability);
+
+ItmRes area_tls_ex(double h_tx__meter,
+                   double h_rx__meter,
+                   int tx_site_criteria,
+                   int rx_site_criteria,
+                   double d__km,
+                   double delta_h__meter,
+                   int climate,
+                   double N_0,
+                   double f__mhz,
+                   int pol,
+                   double epsilon,
+                   double sigma,
+                   int mdvar,
+                   double time,
+                   double location,
+                   double situation);
+
+ItmRes area_cr_ex(double h_tx__meter,
+                  double h_rx__meter,
+                  int tx_site_criteria,
+                  int rx_site_criteria,
+                  double d__km,
+                  double delta_h__meter,
+                  int climate,
+                  double N_0,
+                  double f__mhz,
+                  int pol,
+                  double epsilon,
+                  double sigma,
+                  int mdvar,
+                  double confidence,
+                  double reliability);
+
+double variability(double time,
+                   double location,
+                   double situation,
+                   double h_e_tx__meter,
+                   double h_e_rx__meter,
+                   double delta_h__meter,
+                   double f__mhz,
+                   double d__meter,
+                   double A_ref__db,
+                   int climate,
+                   int mdvar,
+                   int64_t &warnings);
@@ -14,7 +14,7 @@ mod error;
 pub use crate::error::NasademError;
 use byteorder::{BigEndian as BE, ReadBytesExt};
 use geo::{
-    geometry::{Coord, Polygon},
+    geometry::{Coord, Polygon, Rect},
     polygon,
 };
 use memmap2::Mmap;
@@ -286,6 +286,46 @@ impl Tile {
         (0..(self.dimensions.0 * self.dimensions.1)).map(|index| Sample { tile: self, index })
     }
 
+    /// Returns an iterator over samples whose centers lie within
+    /// `rect`.
+    ///
+    /// Unlike [`Tile::iter`], this only visits the rows and columns
+    /// overlapping `rect`.
+    pub fn iter_within(&self, rect: Rect<C>) -> impl Iterator<Item = Sample<'_>> + '_ {
+        let c = ARCSEC_PER_DEG / C::from(self.resolution);
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let range = |min: C, max: C, origin: C, len: usize| {
+            let first = ((min - origin) * c).ceil().max(0.0) as usize;
+            let last = ((max - origin) * c).floor().min(len as C - 1.0);
+            // `last` is negative when `rect` ends before this tile.
+            let end = if last < 0.0 { 0 } else { last as usize + 1 };
+            first..end.max(first)
+        };
+        let cols = range(
+            rect.min().x,
+            rect.max().x,
+            self.sw_corner_center.x,
+            self.dimensions.0,
+        );
+        let rows = range(
+            rect.min().y,
+            rect.max().y,
+            self.sw_corner_center.y,
+            self.dimensions.1,
+        );
+        // Rows are stored north to south.
+        rows.rev().flat_map(move |y| {
+            cols.clone().map(move |x| Sample {
+                tile: self,
+                index: self.xy_to_linear_index((x, y)),
+            })
+        })
+    }
+
     /// Returns this tile's outline as a polygon.
     pub fn polygon(&self) -> Polygon {
         let delta = C::from(self.resolution) * HALF_ARCSEC;
@@ -335,13 +375,16 @@ impl Tile {
         self.dimensions.0 * (self.dimensions.1 - y - 1) + x
     }
 
-    fn xy_to_polygon(&self, (x, y): (usize, usize)) -> Polygon<C> {
+    fn xy_to_center(&self, (x, y): (usize, usize)) -> Coord<C> {
         #[allow(clippy::cast_precision_loss)]
-        let center = Coord {
+        Coord {
             x: self.sw_corner_center.x + (x as C * C::from(self.resolution)) / ARCSEC_PER_DEG,
             y: self.sw_corner_center.y + (y as C * C::from(self.resolution)) / ARCSEC_PER_DEG,
-        };
-        polygon(&center, C::from(self.resolution))
+        }
+    }
+
+    fn xy_to_polygon(&self, (x, y): (usize, usize)) -> Polygon<C> {
+        polygon(&self.xy_to_center((x, y)), C::from(self.resolution))
     }
 }
 
@@ -379,6 +422,12 @@ impl<'a> Sample<'a> {
         self.tile
             .xy_to_polygon(self.tile.linear_index_to_xy(self.index))
     }
+
+    /// Returns the coordinate of this sample's center.
+    pub fn center(&self) -> Coord<C> {
+        self.tile
+            .xy_to_center(self.tile.linear_index_to_xy(self.index))
+    }
 }
 
 impl<'a> std::cmp::PartialEq for Sample<'a> {
@@ -532,8 +581,8 @@ mod _1_arc_second {
 #[cfg(test)]
 mod _3_arc_second {
     use super::{
-        extract_resolution, parse_sw_corner, BufReader, Coord, File, Polygon, ReadBytesExt, Tile,
-        BE,
+        extract_resolution, parse_sw_corner, BufReader, Coord, File, Polygon, ReadBytesExt, Rect,
+        Tile, BE,
     };
     use geo::geometry::LineString;
     use std::path::PathBuf;
@@ -651,4 +700,42 @@ mod _3_arc_second {
             )
         );
     }
+
+    #[test]
+    fn test_iter_within() {
+        let mut path = three_arcsecond_dir();
+        path.push("N44W072.hgt");
+        let tile = Tile::load(&path).unwrap();
+        let rect = Rect::new(
+            Coord {
+                x: -71.310_1,
+                y: 44.260_1,
+            },
+            Coord {
+                x: -71.298_3,
+                y: 44.274_9,
+            },
+        );
+        let expected: Vec<(Coord, i16)> = tile
+            .iter()
+            .map(|sample| (sample.center(), sample.elevation()))
+            .filter(|(center, _)| {
+                rect.min().x <= center.x
+                    && center.x <= rect.max().x
+                    && rect.min().y <= center.y
+                    && center.y <= rect.max().y
+            })
+            .collect();
+        let within: Vec<(Coord, i16)> = tile
+            .iter_within(rect)
+            .map(|sample| (sample.center(), sample.elevation()))
+            .collect();
+        // 15 columns by 17 rows of 3-arcsecond samples.
+        assert_eq!(within.len(), 15 * 17);
+        assert_eq!(within, expected);
+
+        // Rects outside the tile are empty.
+        let west = Rect::new(Coord { x: -73.0, y: 44.5 }, Coord { x: -72.5, y: 44.6 });
+        assert_eq!(tile.iter_within(west).count(), 0);
+    }
 }
@@ -8,4 +8,8 @@ pub enum PropahError {
 
     #[error("{0}")]
     Terrain(#[from] TerrainError),
+
+    #[cfg(feature = "itm")]
+    #[error("{0}")]
+    Itm(#[from] itm::ItmErrCode),
 }
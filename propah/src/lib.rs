@@ -4,6 +4,8 @@
 
 mod error;
 pub mod fresnel;
+#[cfg(feature = "itm")]
+pub mod loss;
 mod math;
 pub mod p2p;
 
@@ -12,5 +14,8 @@ pub use {
     geo, terrain,
 };
 
+#[cfg(feature = "itm")]
+pub use {crate::loss::PathLoss, itm};
+
 /// Speed of light in m/s
 const C: usize = 299_792_458;
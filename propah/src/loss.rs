@@ -0,0 +1,275 @@
+//! Path loss predicted by NTIA's Irregular Terrain Model.
+
+use crate::Point2Point;
+use geo::CoordFloat;
+use itm::{CurveAxis, IntermediateValues, ItmErrCode, ItmParams, LossCurve, Warnings};
+use terrain::{Profile, TerminalHeight};
+
+/// ITM's prediction for a path.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathLoss {
+    /// Basic transmission loss (dB).
+    pub attenuation_db: f64,
+
+    /// How ITM arrived at `attenuation_db`.
+    pub values: IntermediateValues,
+}
+
+impl PathLoss {
+    /// Returns warnings about the validity of this prediction.
+    pub fn warnings(&self) -> Warnings {
+        self.values.warnings
+    }
+}
+
+/// Runs ITM over `profile`'s terrain.
+///
+/// The PFL is built from the raw terrain, as ITM accounts for earth
+/// curvature itself, so this is independent of
+/// [`earth_curve`](terrain::ProfileBuilder::earth_curve). Terminal
+/// heights are taken from the profile, so they are correct whichever
+/// [`Altitude`](terrain::Altitude) the profile was built with.
+pub fn itm_p2p<C>(params: &ItmParams, profile: &Profile<C>) -> Result<PathLoss, ItmErrCode>
+where
+    C: CoordFloat,
+    f64: From<C>,
+{
+    path_loss(
+        params,
+        &profile.distances_m,
+        &profile.terrain_elev_m,
+        profile.start_height,
+        profile.end_height,
+    )
+}
+
+/// Runs ITM over `profile`'s terrain at each percentage along
+/// `axis`.
+///
+/// See [`itm_p2p`] and [`ItmParams::p2p_curve`].
+pub fn itm_p2p_curve<C>(
+    params: &ItmParams,
+    profile: &Profile<C>,
+    axis: CurveAxis,
+) -> Result<LossCurve, ItmErrCode>
+where
+    C: CoordFloat,
+    f64: From<C>,
+{
+    path_loss_curve(
+        params,
+        &profile.distances_m,
+        &profile.terrain_elev_m,
+        profile.start_height,
+        profile.end_height,
+        axis,
+    )
+}
+
+/// ITM prediction.
+impl<T> Point2Point<T>
+where
+    T: CoordFloat,
+    f64: From<T>,
+{
+    /// Runs ITM over this path's terrain.
+    ///
+    /// See [`itm_p2p`].
+    pub fn itm(&self, params: &ItmParams) -> Result<PathLoss, ItmErrCode> {
+        path_loss(
+            params,
+            &self.distances_m,
+            &self.terrain_elev_m,
+            self.start_height,
+            self.end_height,
+        )
+    }
+
+    /// Runs ITM over this path's terrain at each percentage along
+    /// `axis`.
+    ///
+    /// See [`itm_p2p_curve`].
+    pub fn itm_curve(&self, params: &ItmParams, axis: CurveAxis) -> Result<LossCurve, ItmErrCode> {
+        path_loss_curve(
+            params,
+            &self.distances_m,
+            &self.terrain_elev_m,
+            self.start_height,
+            self.end_height,
+            axis,
+        )
+    }
+}
+
+pub(crate) fn path_loss<C>(
+    params: &ItmParams,
+    distances_m: &[C],
+    terrain_elev_m: &[C],
+    start_height: TerminalHeight<C>,
+    end_height: TerminalHeight<C>,
+) -> Result<PathLoss, ItmErrCode>
+where
+    C: CoordFloat,
+    f64: From<C>,
+{
+    let (attenuation_db, values) = params.p2p_ex(
+        start_height.agl_m.into(),
+        end_height.agl_m.into(),
+        step_size_m(distances_m),
+        terrain_elev_m,
+    )?;
+    Ok(PathLoss {
+        attenuation_db,
+        values,
+    })
+}
+
+pub(crate) fn path_loss_curve<C>(
+    params: &ItmParams,
+    distances_m: &[C],
+    terrain_elev_m: &[C],
+    start_height: TerminalHeight<C>,
+    end_height: TerminalHeight<C>,
+    axis: CurveAxis,
+) -> Result<LossCurve, ItmErrCode>
+where
+    C: CoordFloat,
+    f64: From<C>,
+{
+    params.p2p_curve(
+        start_height.agl_m.into(),
+        end_height.agl_m.into(),
+        step_size_m(distances_m),
+        terrain_elev_m,
+        axis,
+    )
+}
+
+/// Returns the distance between uniformly spaced path points.
+fn step_size_m<C>(distances_m: &[C]) -> f64
+where
+    C: CoordFloat,
+    f64: From<C>,
+{
+    // Derive from the total distance rather than the first, most
+    // rounded, step.
+    //
+    // Unwrap is fine as paths always have at least two points.
+    #[allow(clippy::cast_precision_loss)]
+    let step_size_m = f64::from(*distances_m.last().unwrap()) / (distances_m.len() - 1) as f64;
+    step_size_m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::itm_p2p;
+    use crate::Point2Point;
+    use geo::geometry::Coord;
+    use itm::{CurveAxis, ItmParams, Mode, Percentage};
+    use terrain::{Profile, TileMode, Tiles};
+
+    fn tiles() -> Tiles {
+        let tile_dir: std::path::PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "..",
+            "data",
+            "nasadem",
+            "3arcsecond",
+        ]
+        .iter()
+        .collect();
+        Tiles::new(tile_dir, TileMode::MemMap).unwrap()
+    }
+
+    #[test]
+    fn test_point_2_point_path_loss() {
+        let tiles = tiles();
+        let (start, end) = (
+            Coord {
+                x: -71.308_307_164_413_69,
+                y: 44.283_098_066_031_65,
+            },
+            Coord {
+                x: -71.297_207_328_376_8,
+                y: 44.256_280_984_242_78,
+            },
+        );
+        let build = |earth_curve| {
+            Point2Point::builder()
+                .freq(900e6)
+                .start(start)
+                .start_alt(10.0)
+                .max_step(90.0)
+                .end(end)
+                .end_alt(2.0)
+                .earth_curve(earth_curve)
+                .itm(ItmParams::builder())
+                .build(&tiles)
+                .unwrap()
+        };
+        let p2p = build(true);
+        let path_loss = p2p.path_loss.unwrap();
+        let params = ItmParams::builder().freq_hz(900e6).build().unwrap();
+        assert_eq!(p2p.itm(&params).unwrap(), path_loss);
+
+        // Earth curve only affects our own LOS calculations.
+        assert_eq!(build(false).path_loss.unwrap(), path_loss);
+
+        // The path crosses Mt Washington.
+        assert_eq!(path_loss.values.mode, Mode::Diffraction);
+        assert!(path_loss.attenuation_db > path_loss.values.free_space_loss_db);
+        let distance_m = *p2p.distances_m.last().unwrap();
+        assert!((path_loss.values.distance_m - distance_m).abs() < 1e-6);
+
+        // The median of a curve is the default prediction.
+        let median = Percentage::new(50.0).unwrap();
+        let curve = p2p
+            .itm_curve(
+                &params,
+                CurveAxis::Time {
+                    location: median,
+                    situation: median,
+                },
+            )
+            .unwrap();
+        let median_db = curve.attenuation_db(50).unwrap();
+        assert!((median_db - path_loss.attenuation_db).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_profile_path_loss() {
+        let tiles = tiles();
+        let start = Coord {
+            x: -71.308_307_164_413_69,
+            y: 44.283_098_066_031_65,
+        };
+        let end = Coord {
+            x: -71.297_207_328_376_8,
+            y: 44.256_280_984_242_78,
+        };
+        let agl = Profile::<f64>::builder()
+            .start(start)
+            .start_alt(10.0)
+            .max_step(90.0)
+            .end(end)
+            .end_alt(2.0)
+            .build(&tiles)
+            .unwrap();
+        // The same terminals, specified above sea level.
+        let amsl = Profile::<f64>::builder()
+            .start(start)
+            .start_alt_amsl(agl.start_height.amsl_m)
+            .max_step(90.0)
+            .end(end)
+            .end_alt_amsl(agl.end_height.amsl_m)
+            .build(&tiles)
+            .unwrap();
+
+        let params = ItmParams::builder().freq_hz(900e6).build().unwrap();
+        let agl_loss = itm_p2p(&params, &agl).unwrap();
+        let amsl_loss = itm_p2p(&params, &amsl).unwrap();
+        assert!((agl_loss.attenuation_db - amsl_loss.attenuation_db).abs() < 1e-6);
+        assert_eq!(agl_loss.values.mode, amsl_loss.values.mode);
+    }
+}
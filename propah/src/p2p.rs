@@ -4,10 +4,25 @@ use crate::{
     fresnel::{freq_to_wavelen, fresnel},
 };
 use num_traits::{AsPrimitive, Float, FloatConst, FromPrimitive};
-use terrain::{constants::MEAN_EARTH_RADIUS, Profile, Tiles};
+use std::io::{self, Read, Write};
+use terrain::{
+    codec::{read_record, write_record, ColumnFloat},
+    constants::MEAN_EARTH_RADIUS,
+    Altitude, Profile, TerminalHeight, Tiles,
+};
+#[cfg(feature = "itm")]
+use {
+    crate::loss::{path_loss, PathLoss},
+    itm::ItmParamsBuilder,
+};
+
+/// Record kind for [`Point2Point`] in terrain's binary
+/// [`codec`](terrain::codec).
+const P2P_MAGIC: [u8; 4] = *b"P2PF";
 
 /// Point to point propogation estimate.
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Point2Point<T: CoordFloat> {
     /// Incremental path distance for all following vectors.
     pub distances_m: Box<[T]>,
@@ -20,6 +35,12 @@ pub struct Point2Point<T: CoordFloat> {
     /// `start` to `end`.
     pub terrain_elev_m: Box<[T]>,
 
+    /// Height of the earth's surface above the chord from `start` to
+    /// `end` at each step.
+    ///
+    /// All zeros unless built with `earth_curve`.
+    pub earth_bulge_m: Box<[T]>,
+
     /// A straight line from `start` to `end`.
     pub los_elev_m: Box<[T]>,
 
@@ -28,6 +49,19 @@ pub struct Point2Point<T: CoordFloat> {
     /// Due to its usefullness, this is the lower envelope of the
     /// fresnel zone.
     pub lower_fresnel_zone_m: Box<[T]>,
+
+    /// Height of the start of the path.
+    pub start_height: TerminalHeight<T>,
+
+    /// Height of the end of the path.
+    pub end_height: TerminalHeight<T>,
+
+    /// ITM's prediction for this path.
+    ///
+    /// Only computed when built with
+    /// [`itm`](Point2PointBuilder::itm) parameters.
+    #[cfg(feature = "itm")]
+    pub path_loss: Option<PathLoss>,
 }
 
 impl<T> Point2Point<T>
@@ -43,11 +77,12 @@ where
             start: None,
             max_step_m: None,
             end: None,
-            start_alt_m: T::zero(),
-            end_alt_m: T::zero(),
+            start_alt: Altitude::Agl(T::zero()),
+            end_alt: Altitude::Agl(T::zero()),
             earth_curve: false,
-            normalize: false,
             earth_radius: T::from(MEAN_EARTH_RADIUS).unwrap(),
+            #[cfg(feature = "itm")]
+            itm: None,
         }
     }
 
@@ -60,6 +95,26 @@ where
         self.distances_m.len()
     }
 
+    /// Returns an iterator over terrain raised by earth bulge.
+    ///
+    /// See [`Profile::flat_earth_terrain_iter`].
+    pub fn flat_earth_terrain_iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.terrain_elev_m
+            .iter()
+            .zip(self.earth_bulge_m.iter())
+            .map(|(&elev_m, &bulge_m)| elev_m + bulge_m)
+    }
+
+    /// Returns an iterator over LOS bent down by earth bulge.
+    ///
+    /// See [`Profile::curved_los_iter`].
+    pub fn curved_los_iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.los_elev_m
+            .iter()
+            .zip(self.earth_bulge_m.iter())
+            .map(|(&los_m, &bulge_m)| los_m - bulge_m)
+    }
+
     /// Returns an iterator over the elements of the upper fresnel
     /// zone.
     pub fn upper_fresnel_iter(&self) -> impl Iterator<Item = T> + '_
@@ -73,6 +128,82 @@ where
     }
 }
 
+/// Binary encoding.
+impl<T> Point2Point<T>
+where
+    T: ColumnFloat,
+{
+    /// Appends this estimate to `writer`.
+    ///
+    /// See [`terrain::codec`] for a description of the format.
+    pub fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (lons, lats): (Vec<T>, Vec<T>) = self
+            .great_circle
+            .iter()
+            .map(|point| (point.x(), point.y()))
+            .unzip();
+        write_record(
+            writer,
+            P2P_MAGIC,
+            &[
+                self.start_height.agl_m,
+                self.start_height.amsl_m,
+                self.end_height.agl_m,
+                self.end_height.amsl_m,
+            ],
+            &[
+                &self.distances_m,
+                &lons,
+                &lats,
+                &self.terrain_elev_m,
+                &self.earth_bulge_m,
+                &self.los_elev_m,
+                &self.lower_fresnel_zone_m,
+            ],
+        )
+    }
+
+    /// Reads a single estimate, previously written with
+    /// [`Point2Point::encode`], from `reader`.
+    ///
+    /// Path loss is not encoded, so is always `None`.
+    pub fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let (scalars, columns) = read_record(reader, P2P_MAGIC, 4, 7..=7)?;
+        let mut columns = columns.into_iter();
+        // Unwraps are fine as `read_record` returns exactly the
+        // number of columns requested.
+        let mut next = || columns.next().unwrap();
+        let distances_m = next();
+        let great_circle = next()
+            .iter()
+            .zip(next().iter())
+            .map(|(&x, &y)| Point::new(x, y))
+            .collect();
+        let terrain_elev_m = next();
+        let earth_bulge_m = next();
+        let los_elev_m = next();
+        let lower_fresnel_zone_m = next();
+        Ok(Self {
+            distances_m,
+            great_circle,
+            terrain_elev_m,
+            earth_bulge_m,
+            los_elev_m,
+            lower_fresnel_zone_m,
+            start_height: TerminalHeight {
+                agl_m: scalars[0],
+                amsl_m: scalars[1],
+            },
+            end_height: TerminalHeight {
+                agl_m: scalars[2],
+                amsl_m: scalars[3],
+            },
+            #[cfg(feature = "itm")]
+            path_loss: None,
+        })
+    }
+}
+
 pub struct Point2PointBuilder<T: CoordFloat = f32> {
     /// Transmitter frequency (required).
     freq_hz: Option<T>,
@@ -86,22 +217,21 @@ pub struct Point2PointBuilder<T: CoordFloat = f32> {
     /// End point of the path (required).
     end: Option<Coord<T>>,
 
-    /// Starting altitude above ground (meters, defaults to 0).
-    start_alt_m: T,
+    /// Starting altitude (defaults to 0 m AGL).
+    start_alt: Altitude<T>,
 
-    /// Starting altitude above ground (meters, defaults to 0).
-    end_alt_m: T,
+    /// Ending altitude (defaults to 0 m AGL).
+    end_alt: Altitude<T>,
 
-    /// Add earth curvature (defaults to false).
+    /// Compute earth bulge (defaults to false).
     earth_curve: bool,
 
-    /// Place virtual earth curve as the highest and center point of
-    /// the output (defaults to false; has no effect if `earth_curve`
-    /// is `false`).
-    normalize: bool,
-
     /// Earth radius, defaults to [MEAN_EARTH_RADIUS].
     earth_radius: T,
+
+    /// ITM parameters, path loss is only computed if set.
+    #[cfg(feature = "itm")]
+    itm: Option<ItmParamsBuilder>,
 }
 
 impl<T> Point2PointBuilder<T>
@@ -126,7 +256,32 @@ where
     /// Starting altitude above ground (meters, defaults to 0).
     #[must_use]
     pub fn start_alt(mut self, meters: T) -> Self {
-        self.start_alt_m = meters;
+        self.start_alt = Altitude::Agl(meters);
+        self
+    }
+
+    /// Starting altitude above mean sea level (meters).
+    ///
+    /// Building fails if this is below ground.
+    #[must_use]
+    pub fn start_alt_amsl(mut self, meters: T) -> Self {
+        self.start_alt = Altitude::Amsl(meters);
+        self
+    }
+
+    /// Starting altitude above the WGS84 ellipsoid (meters).
+    ///
+    /// Building fails if this is below ground.
+    #[must_use]
+    pub fn start_alt_ellipsoidal(mut self, meters: T) -> Self {
+        self.start_alt = Altitude::Ellipsoidal(meters);
+        self
+    }
+
+    /// Starting altitude (defaults to 0 m AGL).
+    #[must_use]
+    pub fn start_altitude(mut self, altitude: Altitude<T>) -> Self {
+        self.start_alt = altitude;
         self
     }
 
@@ -144,26 +299,42 @@ where
         self
     }
 
-    /// Starting altitude above ground (meters, defaults to 0).
+    /// Ending altitude above ground (meters, defaults to 0).
     #[must_use]
     pub fn end_alt(mut self, meters: T) -> Self {
-        self.end_alt_m = meters;
+        self.end_alt = Altitude::Agl(meters);
         self
     }
 
-    /// Add earth curvature (defaults to false).
+    /// Ending altitude above mean sea level (meters).
+    ///
+    /// Building fails if this is below ground.
     #[must_use]
-    pub fn earth_curve(mut self, add_curve: bool) -> Self {
-        self.earth_curve = add_curve;
+    pub fn end_alt_amsl(mut self, meters: T) -> Self {
+        self.end_alt = Altitude::Amsl(meters);
+        self
+    }
+
+    /// Ending altitude above the WGS84 ellipsoid (meters).
+    ///
+    /// Building fails if this is below ground.
+    #[must_use]
+    pub fn end_alt_ellipsoidal(mut self, meters: T) -> Self {
+        self.end_alt = Altitude::Ellipsoidal(meters);
         self
     }
 
-    /// Place virtual earth curve as the highest and center point of
-    /// the output (defaults to false; has no effect if `earth_curve`
-    /// is `false`).
+    /// Ending altitude (defaults to 0 m AGL).
     #[must_use]
-    pub fn normalize(mut self, normalize: bool) -> Self {
-        self.normalize = normalize;
+    pub fn end_altitude(mut self, altitude: Altitude<T>) -> Self {
+        self.end_alt = altitude;
+        self
+    }
+
+    /// Compute earth bulge (defaults to false).
+    #[must_use]
+    pub fn earth_curve(mut self, add_curve: bool) -> Self {
+        self.earth_curve = add_curve;
         self
     }
 
@@ -174,6 +345,17 @@ where
         self
     }
 
+    /// Compute [`path_loss`](Point2Point::path_loss) with ITM
+    /// (optional).
+    ///
+    /// `params`' frequency is overridden by [`freq`](Self::freq).
+    #[cfg(feature = "itm")]
+    #[must_use]
+    pub fn itm(mut self, params: ItmParamsBuilder) -> Self {
+        self.itm = Some(params);
+        self
+    }
+
     pub fn build(&self, tiles: &Tiles) -> Result<Point2Point<T>, PropahError>
     where
         T: FloatConst + Float + 'static,
@@ -184,20 +366,29 @@ where
         let start = self.start.ok_or(PropahError::Builder("start"))?;
         let max_step_m = self.max_step_m.ok_or(PropahError::Builder("max_step"))?;
         let end = self.end.ok_or(PropahError::Builder("end"))?;
+        #[cfg(feature = "itm")]
+        let itm = self
+            .itm
+            .map(|params| params.freq_hz(freq_hz.into()).build())
+            .transpose()?;
 
         let Profile {
             distances_m,
             great_circle,
             terrain_elev_m,
+            earth_bulge_m,
             los_elev_m,
+            start_height,
+            end_height,
+            clutter: _,
+            footprints: _,
         } = Profile::builder()
             .start(start)
-            .start_alt(self.start_alt_m)
+            .start_altitude(self.start_alt)
             .max_step(max_step_m)
             .end(end)
-            .end_alt(self.end_alt_m)
+            .end_altitude(self.end_alt)
             .earth_curve(self.earth_curve)
-            .normalize(self.normalize)
             .earth_radius(self.earth_radius)
             .build(tiles)?;
 
@@ -216,17 +407,134 @@ where
         assert!(
             distances_m.len() == great_circle.len()
                 && great_circle.len() == terrain_elev_m.len()
-                && terrain_elev_m.len() == los_elev_m.len()
+                && terrain_elev_m.len() == earth_bulge_m.len()
+                && earth_bulge_m.len() == los_elev_m.len()
                 && los_elev_m.len() == fresnel_zone_m.len(),
             "all vectors in report must have the same length"
         );
 
+        #[cfg(feature = "itm")]
+        let path_loss = itm
+            .map(|params| {
+                path_loss(
+                    &params,
+                    &distances_m,
+                    &terrain_elev_m,
+                    start_height,
+                    end_height,
+                )
+            })
+            .transpose()?;
+
         Ok(Point2Point {
             distances_m,
             great_circle,
             terrain_elev_m,
+            earth_bulge_m,
             los_elev_m,
             lower_fresnel_zone_m: fresnel_zone_m,
+            start_height,
+            end_height,
+            #[cfg(feature = "itm")]
+            path_loss,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Point2Point;
+    use geo::geometry::Coord;
+    use std::io::Cursor;
+    use terrain::{TileMode, Tiles};
+
+    fn point_2_point() -> Point2Point<f64> {
+        let tile_dir: std::path::PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "..",
+            "data",
+            "nasadem",
+            "3arcsecond",
+        ]
+        .iter()
+        .collect();
+        let tiles = Tiles::new(tile_dir, TileMode::MemMap).unwrap();
+        Point2Point::builder()
+            .freq(900e6)
+            .start(Coord {
+                x: -71.308_307_164_413_69,
+                y: 44.283_098_066_031_65,
+            })
+            .start_alt(10.0)
+            .max_step(90.0)
+            .end(Coord {
+                x: -71.297_207_328_376_8,
+                y: 44.256_280_984_242_78,
+            })
+            .end_alt(2.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let p2p = point_2_point();
+        let mut buf = Vec::new();
+        p2p.encode(&mut buf).unwrap();
+        p2p.encode(&mut buf).unwrap();
+
+        let mut reader = Cursor::new(buf);
+        for _ in 0..2 {
+            let decoded = Point2Point::<f64>::decode(&mut reader).unwrap();
+            let bits = |p2p: &Point2Point<f64>| -> Vec<u64> {
+                p2p.distances_m
+                    .iter()
+                    .chain(
+                        p2p.great_circle
+                            .iter()
+                            .flat_map(|point| [&point.0.x, &point.0.y]),
+                    )
+                    .chain(p2p.terrain_elev_m.iter())
+                    .chain(p2p.earth_bulge_m.iter())
+                    .chain(p2p.los_elev_m.iter())
+                    .chain(p2p.lower_fresnel_zone_m.iter())
+                    .chain([
+                        &p2p.start_height.agl_m,
+                        &p2p.start_height.amsl_m,
+                        &p2p.end_height.agl_m,
+                        &p2p.end_height.amsl_m,
+                    ])
+                    .map(|value| value.to_bits())
+                    .collect()
+            };
+            assert_eq!(bits(&p2p), bits(&decoded));
+        }
+
+        // A `Profile` record is not a `Point2Point` record.
+        let mut buf = Vec::new();
+        terrain::Profile {
+            distances_m: p2p.distances_m.clone(),
+            great_circle: p2p.great_circle.clone(),
+            terrain_elev_m: p2p.terrain_elev_m.clone(),
+            earth_bulge_m: p2p.earth_bulge_m.clone(),
+            los_elev_m: p2p.los_elev_m.clone(),
+            start_height: p2p.start_height,
+            end_height: p2p.end_height,
+            clutter: None,
+            footprints: None,
+        }
+        .encode(&mut buf)
+        .unwrap();
+        assert!(Point2Point::<f64>::decode(&mut Cursor::new(buf)).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let p2p = point_2_point();
+        let json = serde_json::to_string(&p2p).unwrap();
+        let decoded: Point2Point<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(p2p, decoded);
+    }
+}
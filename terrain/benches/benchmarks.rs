@@ -52,7 +52,6 @@ fn memmap_terrain_profile_f32(c: &mut Criterion) {
                     .max_step(*d)
                     .end(*e)
                     .earth_curve(true)
-                    .normalize(true)
                     .build(t)
                     .unwrap()
             });
@@ -69,7 +68,6 @@ fn memmap_terrain_profile_f32(c: &mut Criterion) {
                     .max_step(*d)
                     .end(*e)
                     .earth_curve(true)
-                    .normalize(true)
                     .build(t)
                     .unwrap()
             });
@@ -86,7 +84,6 @@ fn memmap_terrain_profile_f32(c: &mut Criterion) {
                     .max_step(*d)
                     .end(*e)
                     .earth_curve(true)
-                    .normalize(true)
                     .build(t)
                     .unwrap()
             });
@@ -127,7 +124,6 @@ fn memmap_terrain_profile_f64(c: &mut Criterion) {
                     .max_step(*d)
                     .end(*e)
                     .earth_curve(true)
-                    .normalize(true)
                     .build(t)
                     .unwrap()
             });
@@ -144,7 +140,6 @@ fn memmap_terrain_profile_f64(c: &mut Criterion) {
                     .max_step(*d)
                     .end(*e)
                     .earth_curve(true)
-                    .normalize(true)
                     .build(t)
                     .unwrap()
             });
@@ -161,7 +156,6 @@ fn memmap_terrain_profile_f64(c: &mut Criterion) {
                     .max_step(*d)
                     .end(*e)
                     .earth_curve(true)
-                    .normalize(true)
                     .build(t)
                     .unwrap()
             });
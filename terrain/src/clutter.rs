@@ -0,0 +1,412 @@
+//! Land cover (clutter) layer.
+
+use crate::{tiles::C, TerrainError};
+use geo::geometry::Coord;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::Path,
+};
+use tiff::{
+    decoder::{Decoder, DecodingResult, Limits},
+    tags::Tag,
+    ColorType,
+};
+
+/// Class code used where no raster provides coverage.
+pub const NO_CLASS: u8 = 0;
+
+/// GeoTIFF `GTModelTypeGeoKey`.
+const MODEL_TYPE_GEO_KEY: u16 = 1024;
+
+/// GeoTIFF `ModelTypeGeographic`.
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+
+/// GeoTIFF `GTRasterTypeGeoKey`.
+const RASTER_TYPE_GEO_KEY: u16 = 1025;
+
+/// GeoTIFF `RasterPixelIsPoint`.
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+/// Properties of a single land cover class.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClutterClass {
+    /// Typical height of the clutter above ground (meters).
+    pub height_m: f32,
+
+    /// Excess loss attributed to this class (dB).
+    pub attenuation_db: f32,
+}
+
+/// Maps raster class codes to clutter properties.
+///
+/// Classes missing from the table have no height or attenuation.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClutterTable {
+    classes: HashMap<u8, ClutterClass>,
+}
+
+impl ClutterTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds, or replaces, class `code`.
+    #[must_use]
+    pub fn class(mut self, code: u8, height_m: f32, attenuation_db: f32) -> Self {
+        self.classes.insert(
+            code,
+            ClutterClass {
+                height_m,
+                attenuation_db,
+            },
+        );
+        self
+    }
+
+    /// Returns the properties of class `code`, if known.
+    pub fn get(&self, code: u8) -> Option<ClutterClass> {
+        self.classes.get(&code).copied()
+    }
+
+    /// Returns the clutter height of class `code`, or 0 if unknown.
+    pub fn height_m(&self, code: u8) -> f32 {
+        self.get(code).map_or(0.0, |class| class.height_m)
+    }
+
+    /// Returns the attenuation of class `code`, or 0 if unknown.
+    pub fn attenuation_db(&self, code: u8) -> f32 {
+        self.get(code).map_or(0.0, |class| class.attenuation_db)
+    }
+}
+
+/// A single band, 8-bit GeoTIFF of land cover class codes, such as
+/// ESA WorldCover.
+///
+/// The raster must be in geographic (longitude/latitude)
+/// coordinates. Projected products, like NLCD, need to be reprojected
+/// first (e.g., with `gdalwarp -t_srs EPSG:4326`).
+pub struct ClutterRaster {
+    /// Coordinate of the north-west corner of the north-west pixel.
+    nw_corner: Coord<C>,
+
+    /// Width and height of each pixel in degrees.
+    pixel_size_deg: (C, C),
+
+    /// Number of columns.
+    width: usize,
+
+    /// Number of rows.
+    height: usize,
+
+    /// Class codes, row major starting with the northernmost row.
+    classes: Box<[u8]>,
+}
+
+impl ClutterRaster {
+    /// Reads a GeoTIFF from disk.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, TerrainError> {
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Reads a GeoTIFF from `reader`.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, TerrainError> {
+        // Global land cover tiles are often larger than the decoder's
+        // default limits.
+        let mut decoder = Decoder::new(reader)?.with_limits(Limits::unlimited());
+
+        if !matches!(
+            decoder.colortype()?,
+            ColorType::Gray(8) | ColorType::Palette(8)
+        ) {
+            return Err(TerrainError::Clutter("expected single band 8-bit classes"));
+        }
+
+        let geo_keys = decoder.get_tag_u16_vec(Tag::GeoKeyDirectoryTag)?;
+        let geo_key = |id: u16| {
+            // Header is 4 shorts, followed by 4 shorts per key, where
+            // the value is stored inline if the location is 0.
+            geo_keys
+                .get(4..)
+                .unwrap_or_default()
+                .chunks_exact(4)
+                .find(|entry| entry[0] == id && entry[1] == 0)
+                .map(|entry| entry[3])
+        };
+        if geo_key(MODEL_TYPE_GEO_KEY) != Some(MODEL_TYPE_GEOGRAPHIC) {
+            return Err(TerrainError::Clutter("expected geographic coordinates"));
+        }
+
+        let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag)?;
+        let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag)?;
+        let (&[scale_x, scale_y, ..], &[tie_i, tie_j, _, tie_x, tie_y, ..]) =
+            (scale.as_slice(), tiepoint.as_slice())
+        else {
+            return Err(TerrainError::Clutter("malformed georeferencing tags"));
+        };
+        // Point rasters are tied to pixel centers rather than corners.
+        let offset = if geo_key(RASTER_TYPE_GEO_KEY) == Some(RASTER_PIXEL_IS_POINT) {
+            0.5
+        } else {
+            0.0
+        };
+        let nw_corner = Coord {
+            x: tie_x - (tie_i + offset) * scale_x,
+            y: tie_y + (tie_j + offset) * scale_y,
+        };
+
+        let (width, height) = decoder.dimensions()?;
+        let DecodingResult::U8(classes) = decoder.read_image()? else {
+            return Err(TerrainError::Clutter("expected single band 8-bit classes"));
+        };
+
+        Ok(Self {
+            nw_corner,
+            pixel_size_deg: (scale_x, scale_y),
+            width: width as usize,
+            height: height as usize,
+            classes: classes.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the class at `coord`, or `None` if `coord` is outside
+    /// this raster.
+    pub fn get(&self, coord: Coord<C>) -> Option<u8> {
+        let col = ((coord.x - self.nw_corner.x) / self.pixel_size_deg.0).floor();
+        let row = ((self.nw_corner.y - coord.y) / self.pixel_size_deg.1).floor();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (col, row) = (col as usize, row as usize);
+        let in_bounds = coord.x >= self.nw_corner.x
+            && coord.y <= self.nw_corner.y
+            && col < self.width
+            && row < self.height;
+        in_bounds.then(|| self.classes[row * self.width + col])
+    }
+}
+
+impl std::fmt::Debug for ClutterRaster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClutterRaster")
+            .field("nw_corner", &self.nw_corner)
+            .field("pixel_size_deg", &self.pixel_size_deg)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Land cover rasters and the properties of their classes.
+#[derive(Debug)]
+pub struct Clutter {
+    rasters: Vec<ClutterRaster>,
+    table: ClutterTable,
+}
+
+impl Clutter {
+    pub fn new(table: ClutterTable) -> Self {
+        Self {
+            rasters: Vec::new(),
+            table,
+        }
+    }
+
+    /// Adds a raster. Where rasters overlap, the first one added
+    /// wins.
+    #[must_use]
+    pub fn raster(mut self, raster: ClutterRaster) -> Self {
+        self.rasters.push(raster);
+        self
+    }
+
+    /// Returns this layer's class table.
+    pub fn table(&self) -> &ClutterTable {
+        &self.table
+    }
+
+    /// Returns the class at `coord`, or [`NO_CLASS`] if no raster
+    /// covers it.
+    pub fn class(&self, coord: Coord<C>) -> u8 {
+        self.rasters
+            .iter()
+            .find_map(|raster| raster.get(coord))
+            .unwrap_or(NO_CLASS)
+    }
+
+    /// Returns the clutter height at `coord`.
+    pub fn height_m(&self, coord: Coord<C>) -> f32 {
+        self.table.height_m(self.class(coord))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clutter, ClutterRaster, ClutterTable, NO_CLASS};
+    use crate::{Profile, TileMode, Tiles};
+    use geo::geometry::Coord;
+    use std::{io::Cursor, sync::Arc};
+    use tiff::{
+        encoder::{colortype::Gray8, TiffEncoder},
+        tags::Tag,
+    };
+
+    const TREES: u8 = 10;
+    const BUILT_UP: u8 = 50;
+
+    /// Returns a 4x2 pixel, 0.01° resolution raster whose north-west
+    /// corner is at `nw_corner`, with trees to the west and buildings
+    /// to the east.
+    fn geotiff(nw_corner: Coord, model_type: u16) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut buf).unwrap();
+        let mut image = encoder.new_image::<Gray8>(4, 2).unwrap();
+        let dir = image.encoder();
+        dir.write_tag(Tag::ModelPixelScaleTag, &[0.01, 0.01, 0.0][..])
+            .unwrap();
+        dir.write_tag(
+            Tag::ModelTiepointTag,
+            &[0.0, 0.0, 0.0, nw_corner.x, nw_corner.y, 0.0][..],
+        )
+        .unwrap();
+        dir.write_tag(
+            Tag::GeoKeyDirectoryTag,
+            &[1_u16, 1, 0, 2, 1024, 0, 1, model_type, 1025, 0, 1, 1][..],
+        )
+        .unwrap();
+        let classes = [
+            TREES, TREES, BUILT_UP, BUILT_UP, //
+            TREES, TREES, BUILT_UP, BUILT_UP,
+        ];
+        image.write_data(&classes).unwrap();
+        buf.into_inner()
+    }
+
+    #[test]
+    fn test_raster_lookup() {
+        let nw_corner = Coord {
+            x: -71.32,
+            y: 44.28,
+        };
+        let raster = ClutterRaster::from_reader(Cursor::new(geotiff(nw_corner, 2))).unwrap();
+        assert_eq!(
+            raster.get(Coord {
+                x: -71.315,
+                y: 44.275
+            }),
+            Some(TREES)
+        );
+        assert_eq!(
+            raster.get(Coord {
+                x: -71.285,
+                y: 44.265
+            }),
+            Some(BUILT_UP)
+        );
+        assert_eq!(
+            raster.get(Coord {
+                x: -71.325,
+                y: 44.275
+            }),
+            None
+        );
+        assert_eq!(
+            raster.get(Coord {
+                x: -71.275,
+                y: 44.275
+            }),
+            None
+        );
+        assert_eq!(
+            raster.get(Coord {
+                x: -71.315,
+                y: 44.285
+            }),
+            None
+        );
+        assert_eq!(
+            raster.get(Coord {
+                x: -71.315,
+                y: 44.255
+            }),
+            None
+        );
+
+        // Projected rasters are rejected.
+        assert!(matches!(
+            ClutterRaster::from_reader(Cursor::new(geotiff(nw_corner, 1))),
+            Err(crate::TerrainError::Clutter(_))
+        ));
+    }
+
+    #[test]
+    fn test_profile_clutter() {
+        let table = ClutterTable::new()
+            .class(TREES, 15.0, 8.0)
+            .class(BUILT_UP, 10.0, 12.0);
+        assert_eq!(table.attenuation_db(BUILT_UP), 12.0);
+        assert_eq!(table.height_m(NO_CLASS), 0.0);
+
+        let raster = ClutterRaster::from_reader(Cursor::new(geotiff(
+            Coord {
+                x: -71.32,
+                y: 44.28,
+            },
+            2,
+        )))
+        .unwrap();
+        let clutter = Arc::new(Clutter::new(table).raster(raster));
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+
+        // West to east through the raster, starting and ending
+        // outside of it.
+        let profile = Profile::builder()
+            .start(Coord {
+                x: -71.33,
+                y: 44.27,
+            })
+            .max_step(90.0)
+            .end(Coord {
+                x: -71.27,
+                y: 44.27,
+            })
+            .clutter(clutter.clone())
+            .build(&tiles)
+            .unwrap();
+        let profile_clutter = profile.clutter.as_ref().unwrap();
+
+        let classes = &profile_clutter.classes;
+        assert_eq!(classes.first(), Some(&NO_CLASS));
+        assert_eq!(classes.last(), Some(&NO_CLASS));
+        assert!(classes.contains(&TREES));
+        assert!(classes.contains(&BUILT_UP));
+        // Classes don't go back to trees after the first building.
+        let first_building = classes.iter().position(|c| *c == BUILT_UP).unwrap();
+        assert!(!classes[first_building..].contains(&TREES));
+
+        for ((class, surface_m), terrain_m) in classes
+            .iter()
+            .zip(profile_clutter.surface_elev_m.iter())
+            .zip(profile.terrain_elev_m.iter())
+        {
+            assert_eq!(*surface_m, terrain_m + clutter.table().height_m(*class));
+        }
+
+        let bare = Profile::builder()
+            .start(Coord {
+                x: -71.33,
+                y: 44.27,
+            })
+            .max_step(90.0)
+            .end(Coord {
+                x: -71.27,
+                y: 44.27,
+            })
+            .build(&tiles)
+            .unwrap();
+        assert!(bare.clutter.is_none());
+        assert_eq!(bare.terrain_elev_m, profile.terrain_elev_m);
+    }
+}
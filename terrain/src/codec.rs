@@ -0,0 +1,612 @@
+//! Compact columnar binary encoding.
+//!
+//! Each record is laid out as follows, with all numbers little
+//! endian:
+//!
+//! | field   | bytes                     | description                              |
+//! |---------|---------------------------|------------------------------------------|
+//! | magic   | 4                         | record kind, e.g. `TPRF` for [`Profile`] |
+//! | version | 1                         | format version, currently 2              |
+//! | width   | 1                         | bytes per value, 4 (`f32`) or 8 (`f64`)  |
+//! | scalars | 1                         | number of scalar values                  |
+//! | columns | 1                         | number of columns                        |
+//! | len     | 4                         | number of values in each column          |
+//! | data    | `scalars * width`         | scalar values                            |
+//! | data    | `columns * len * width`   | each column's values, one after another  |
+//!
+//! A [`Profile`] has 6 columns, plus 2 more (clutter classes and
+//! surface elevation) when it was built with a clutter layer, and 6
+//! more when it was built with footprints. Footprint ids are split
+//! into 4 columns of 16 bits each, so every id is exactly
+//! representable as an `f32`, with -1 in all 4 marking steps without
+//! a structure. Structure heights and surface elevation follow.
+//!
+//! Version 1 records stored earth bulge in the terrain column, so
+//! they are rejected rather than misread as raw terrain.
+//!
+//! Records can be concatenated to archive many profiles in a single
+//! file. Floats are stored as-is, so decoding reproduces the encoded
+//! values bit for bit.
+
+use crate::{Profile, ProfileClutter, ProfileFootprints, TerminalHeight};
+use byteorder::{LittleEndian as LE, ReadBytesExt, WriteBytesExt};
+use geo::{geometry::Point, CoordFloat};
+use std::{
+    io::{self, Read, Write},
+    ops::RangeInclusive,
+};
+
+/// Current format version.
+const VERSION: u8 = 2;
+
+/// Record kind for [`Profile`].
+const PROFILE_MAGIC: [u8; 4] = *b"TPRF";
+
+/// Number of columns every [`Profile`] record has.
+const PROFILE_COLUMNS: u8 = 6;
+
+/// Number of additional columns for [`ProfileClutter`].
+const CLUTTER_COLUMNS: u8 = 2;
+
+/// Number of 16-bit columns a footprint id is split into.
+const ID_COLUMNS: u8 = 4;
+
+/// Number of additional columns for [`ProfileFootprints`].
+const FOOTPRINT_COLUMNS: u8 = ID_COLUMNS + 2;
+
+/// A float type which can be stored in a column.
+pub trait ColumnFloat: CoordFloat {
+    /// Number of bytes per value.
+    const WIDTH: u8;
+
+    fn write_to<W: Write>(self, writer: &mut W) -> io::Result<()>;
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+impl ColumnFloat for f32 {
+    const WIDTH: u8 = 4;
+
+    fn write_to<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_f32::<LE>(self)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        reader.read_f32::<LE>()
+    }
+}
+
+impl ColumnFloat for f64 {
+    const WIDTH: u8 = 8;
+
+    fn write_to<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_f64::<LE>(self)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        reader.read_f64::<LE>()
+    }
+}
+
+/// Writes a single record of `scalars` and equal length `columns`.
+///
+/// Writes are small, so `writer` should be buffered.
+///
+/// # Panics
+///
+/// Panics if `columns` have different lengths.
+pub fn write_record<W, C>(
+    writer: &mut W,
+    magic: [u8; 4],
+    scalars: &[C],
+    columns: &[&[C]],
+) -> io::Result<()>
+where
+    W: Write,
+    C: ColumnFloat,
+{
+    let len = columns.first().map_or(0, |column| column.len());
+    assert!(
+        columns.iter().all(|column| column.len() == len),
+        "all columns must have the same length"
+    );
+    let scalar_count = u8::try_from(scalars.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many scalars"))?;
+    let column_count = u8::try_from(columns.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many columns"))?;
+    let len = u32::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "column too long"))?;
+
+    writer.write_all(&magic)?;
+    writer.write_u8(VERSION)?;
+    writer.write_u8(C::WIDTH)?;
+    writer.write_u8(scalar_count)?;
+    writer.write_u8(column_count)?;
+    writer.write_u32::<LE>(len)?;
+    for value in scalars {
+        value.write_to(writer)?;
+    }
+    for column in columns {
+        for value in *column {
+            value.write_to(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// A decoded record's scalars and columns.
+pub type Record<C> = (Box<[C]>, Vec<Box<[C]>>);
+
+/// Reads a single record, which must be of kind `magic` and contain
+/// `scalars` scalars and a number of columns of `C` within
+/// `columns`.
+///
+/// Reads are small, so `reader` should be buffered.
+pub fn read_record<R, C>(
+    reader: &mut R,
+    magic: [u8; 4],
+    scalars: u8,
+    columns: RangeInclusive<u8>,
+) -> io::Result<Record<C>>
+where
+    R: Read,
+    C: ColumnFloat,
+{
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut found_magic = [0; 4];
+    reader.read_exact(&mut found_magic)?;
+    if found_magic != magic {
+        return Err(invalid(format!(
+            "expected record kind {magic:?}, found {found_magic:?}"
+        )));
+    }
+    let version = reader.read_u8()?;
+    if version != VERSION {
+        return Err(invalid(format!("unsupported format version {version}")));
+    }
+    let width = reader.read_u8()?;
+    if width != C::WIDTH {
+        return Err(invalid(format!(
+            "expected {}-byte values, found {width}-byte",
+            C::WIDTH
+        )));
+    }
+    let found_scalars = reader.read_u8()?;
+    if found_scalars != scalars {
+        return Err(invalid(format!(
+            "expected {scalars} scalars, found {found_scalars}"
+        )));
+    }
+    let found_columns = reader.read_u8()?;
+    if !columns.contains(&found_columns) {
+        return Err(invalid(format!(
+            "expected {} to {} columns, found {found_columns}",
+            columns.start(),
+            columns.end()
+        )));
+    }
+    let len = reader.read_u32::<LE>()?;
+
+    let scalars = (0..scalars)
+        .map(|_| C::read_from(reader))
+        .collect::<io::Result<_>>()?;
+    let columns = (0..found_columns)
+        .map(|_| (0..len).map(|_| C::read_from(reader)).collect())
+        .collect::<io::Result<_>>()?;
+    Ok((scalars, columns))
+}
+
+/// Binary encoding.
+impl<C> Profile<C>
+where
+    C: ColumnFloat,
+{
+    /// Appends this profile to `writer`.
+    ///
+    /// See the [`codec`](crate::codec) module for a description of the
+    /// format.
+    pub fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (lons, lats): (Vec<C>, Vec<C>) = self
+            .great_circle
+            .iter()
+            .map(|point| (point.x(), point.y()))
+            .unzip();
+        let clutter = self.clutter.as_ref().map(|clutter| {
+            let classes: Vec<C> = clutter
+                .classes
+                .iter()
+                .map(|&class| C::from(class).unwrap())
+                .collect();
+            (classes, &clutter.surface_elev_m)
+        });
+        let mut columns: Vec<&[C]> = vec![
+            &self.distances_m,
+            &lons,
+            &lats,
+            &self.terrain_elev_m,
+            &self.earth_bulge_m,
+            &self.los_elev_m,
+        ];
+        if let Some((classes, surface_elev_m)) = &clutter {
+            columns.push(classes);
+            columns.push(surface_elev_m);
+        }
+        let footprints = self.footprints.as_ref().map(|footprints| {
+            let ids: Vec<Vec<C>> = (0..ID_COLUMNS)
+                .map(|chunk| {
+                    footprints
+                        .ids
+                        .iter()
+                        .map(|id| match id {
+                            Some(id) => C::from((id >> (16 * chunk)) & 0xffff).unwrap(),
+                            None => -C::one(),
+                        })
+                        .collect()
+                })
+                .collect();
+            (ids, &footprints.heights_m, &footprints.surface_elev_m)
+        });
+        if let Some((ids, heights_m, surface_elev_m)) = &footprints {
+            columns.extend(ids.iter().map(Vec::as_slice));
+            columns.push(heights_m);
+            columns.push(surface_elev_m);
+        }
+        write_record(
+            writer,
+            PROFILE_MAGIC,
+            &[
+                self.start_height.agl_m,
+                self.start_height.amsl_m,
+                self.end_height.agl_m,
+                self.end_height.amsl_m,
+            ],
+            &columns,
+        )
+    }
+
+    /// Reads a single profile, previously written with
+    /// [`Profile::encode`], from `reader`.
+    pub fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let (scalars, columns) = read_record::<_, C>(
+            reader,
+            PROFILE_MAGIC,
+            4,
+            PROFILE_COLUMNS..=PROFILE_COLUMNS + CLUTTER_COLUMNS + FOOTPRINT_COLUMNS,
+        )?;
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let extra = columns.len() - usize::from(PROFILE_COLUMNS);
+        let (has_clutter, has_footprints) = match u8::try_from(extra).unwrap() {
+            0 => (false, false),
+            CLUTTER_COLUMNS => (true, false),
+            FOOTPRINT_COLUMNS => (false, true),
+            n if n == CLUTTER_COLUMNS + FOOTPRINT_COLUMNS => (true, true),
+            _ => return Err(invalid(format!("unexpected {} columns", columns.len()))),
+        };
+        let mut columns = columns.into_iter();
+        // Unwraps are fine as the number of columns was checked
+        // above.
+        let mut next = || columns.next().unwrap();
+        let distances_m = next();
+        let great_circle = next()
+            .iter()
+            .zip(next().iter())
+            .map(|(&x, &y)| Point::new(x, y))
+            .collect();
+        let terrain_elev_m = next();
+        let earth_bulge_m = next();
+        let los_elev_m = next();
+        let clutter = if has_clutter {
+            let classes = next()
+                .iter()
+                .map(|&class| {
+                    class
+                        .to_u8()
+                        .filter(|byte| C::from(*byte) == Some(class))
+                        .ok_or_else(|| invalid(format!("invalid clutter class {class:?}")))
+                })
+                .collect::<io::Result<_>>()?;
+            Some(ProfileClutter {
+                classes,
+                surface_elev_m: next(),
+            })
+        } else {
+            None
+        };
+        let footprints = if has_footprints {
+            let chunks: Vec<Box<[C]>> = (0..ID_COLUMNS).map(|_| next()).collect();
+            let ids = (0..distances_m.len())
+                .map(|idx| {
+                    if chunks.iter().all(|column| column[idx] == -C::one()) {
+                        return Ok(None);
+                    }
+                    chunks
+                        .iter()
+                        .enumerate()
+                        .try_fold(0_u64, |id, (chunk, column)| {
+                            let bits = column[idx]
+                                .to_u64()
+                                .filter(|bits| C::from(*bits) == Some(column[idx]))
+                                .filter(|bits| *bits <= 0xffff)?;
+                            Some(id | bits << (16 * chunk))
+                        })
+                        .map(Some)
+                        .ok_or_else(|| invalid(format!("invalid footprint id at step {idx}")))
+                })
+                .collect::<io::Result<_>>()?;
+            Some(ProfileFootprints {
+                ids,
+                heights_m: next(),
+                surface_elev_m: next(),
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            distances_m,
+            great_circle,
+            terrain_elev_m,
+            earth_bulge_m,
+            los_elev_m,
+            start_height: TerminalHeight {
+                agl_m: scalars[0],
+                amsl_m: scalars[1],
+            },
+            end_height: TerminalHeight {
+                agl_m: scalars[2],
+                amsl_m: scalars[3],
+            },
+            clutter,
+            footprints,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_record, ColumnFloat, PROFILE_MAGIC};
+    use crate::{Profile, ProfileClutter, ProfileFootprints, TerminalHeight, TileMode, Tiles};
+    use geo::geometry::Coord;
+    use num_traits::Float;
+    use std::io::{Cursor, ErrorKind};
+
+    fn profiles<C>(tiles: &Tiles) -> Vec<Profile<C>>
+    where
+        C: ColumnFloat + num_traits::FromPrimitive + num_traits::FloatConst,
+        C: num_traits::AsPrimitive<usize> + Send + Sync,
+        f64: From<C>,
+    {
+        let summit = Coord {
+            x: C::from(-71.303_25).unwrap(),
+            y: C::from(44.270_5).unwrap(),
+        };
+        let ends = [
+            Coord {
+                x: C::from(-71.297_207_328_376_8).unwrap(),
+                y: C::from(44.256_280_984_242_78).unwrap(),
+            },
+            Coord {
+                x: C::from(-71.4).unwrap(),
+                y: C::from(44.3).unwrap(),
+            },
+        ];
+        Profile::builder()
+            .start(summit)
+            .start_alt(C::from(10.0).unwrap())
+            .max_step(C::from(90.0).unwrap())
+            .earth_curve(true)
+            .build_one_to_many(tiles, &ends)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    /// Returns the exact representation of every value in `profile`.
+    fn bits<C: ColumnFloat>(profile: &Profile<C>) -> Vec<(u64, i16, i8)> {
+        let coords = profile
+            .great_circle
+            .iter()
+            .flat_map(|point| [point.x(), point.y()]);
+        profile
+            .distances_m
+            .iter()
+            .copied()
+            .chain(coords)
+            .chain(profile.terrain_elev_m.iter().copied())
+            .chain(profile.earth_bulge_m.iter().copied())
+            .chain(profile.los_elev_m.iter().copied())
+            .chain([
+                profile.start_height.agl_m,
+                profile.start_height.amsl_m,
+                profile.end_height.agl_m,
+                profile.end_height.amsl_m,
+            ])
+            .map(Float::integer_decode)
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+
+        let profiles_f32 = profiles::<f32>(&tiles);
+        let mut buf = Vec::new();
+        for profile in &profiles_f32 {
+            profile.encode(&mut buf).unwrap();
+        }
+        let mut reader = Cursor::new(buf);
+        for profile in &profiles_f32 {
+            let decoded = Profile::<f32>::decode(&mut reader).unwrap();
+            assert_eq!(bits(profile), bits(&decoded));
+        }
+        assert_eq!(
+            Profile::<f32>::decode(&mut reader).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        let profiles_f64 = profiles::<f64>(&tiles);
+        let mut buf = Vec::new();
+        profiles_f64[0].encode(&mut buf).unwrap();
+        // 12 byte header, 4 scalars, then 6 columns of 8 byte values.
+        assert_eq!(
+            buf.len(),
+            12 + 4 * 8 + 6 * 8 * profiles_f64[0].distances_m.len()
+        );
+        let decoded = Profile::<f64>::decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(bits(&profiles_f64[0]), bits(&decoded));
+
+        // Clutter adds 2 columns.
+        let mut profile = profiles_f64[0].clone();
+        let len = profile.distances_m.len();
+        profile.clutter = Some(ProfileClutter {
+            classes: (0..len).map(|i| (i % 3) as u8 * 10).collect(),
+            surface_elev_m: profile
+                .terrain_elev_m
+                .iter()
+                .map(|elev| elev + 12.5)
+                .collect(),
+        });
+        let mut buf = Vec::new();
+        profile.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), 12 + 4 * 8 + 8 * 8 * len);
+        let decoded = Profile::<f64>::decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(profile, decoded);
+
+        // Footprints add 6 more, and ids survive even as `f32`.
+        let ids: Box<[Option<u64>]> = (0..len)
+            .map(|i| match i % 3 {
+                0 => None,
+                1 => Some(u64::MAX - i as u64),
+                _ => Some(i as u64),
+            })
+            .collect();
+        let heights_m: Box<[f32]> = (0..len).map(|i| (i % 3) as f32 * 7.25).collect();
+        let mut profile = profiles_f32[0].clone();
+        profile.footprints = Some(ProfileFootprints {
+            ids,
+            surface_elev_m: profile
+                .terrain_elev_m
+                .iter()
+                .zip(heights_m.iter())
+                .map(|(elev, height)| elev + height)
+                .collect(),
+            heights_m,
+        });
+        let mut buf = Vec::new();
+        profile.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), 12 + 4 * 4 + 12 * 4 * len);
+        let decoded = Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(profile, decoded);
+
+        // Along with clutter.
+        profile.clutter = Some(ProfileClutter {
+            classes: vec![50; len].into(),
+            surface_elev_m: profile.terrain_elev_m.clone(),
+        });
+        let mut buf = Vec::new();
+        profile.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), 12 + 4 * 4 + 14 * 4 * len);
+        let decoded = Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(profile, decoded);
+    }
+
+    #[test]
+    fn test_rejects_mismatched_records() {
+        let profile = Profile::<f64> {
+            distances_m: vec![0.0, 1.0].into(),
+            great_circle: vec![geo::Point::new(0.0, 0.0); 2].into(),
+            terrain_elev_m: vec![0.0, 0.0].into(),
+            earth_bulge_m: vec![0.0, 0.0].into(),
+            los_elev_m: vec![0.0, 0.0].into(),
+            start_height: TerminalHeight {
+                agl_m: 0.0,
+                amsl_m: 0.0,
+            },
+            end_height: TerminalHeight {
+                agl_m: 0.0,
+                amsl_m: 0.0,
+            },
+            clutter: None,
+            footprints: None,
+        };
+        let mut buf = Vec::new();
+        profile.encode(&mut buf).unwrap();
+
+        let err = Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = read_record::<_, f64>(&mut Cursor::new(&buf), *b"P2PF", 4, 6..=6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err =
+            read_record::<_, f64>(&mut Cursor::new(&buf), PROFILE_MAGIC, 2, 6..=6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err =
+            read_record::<_, f64>(&mut Cursor::new(&buf), PROFILE_MAGIC, 4, 7..=8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Version 1's terrain column had earth bulge added.
+        let mut v1 = buf.clone();
+        v1[4] = 1;
+        let err = Profile::<f64>::decode(&mut Cursor::new(&v1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_rejects_corrupt_values() {
+        let mut profile = Profile::<f32> {
+            distances_m: vec![0.0, 1.0].into(),
+            great_circle: vec![geo::Point::new(0.0, 0.0); 2].into(),
+            terrain_elev_m: vec![0.0, 0.0].into(),
+            earth_bulge_m: vec![0.0, 0.0].into(),
+            los_elev_m: vec![0.0, 0.0].into(),
+            start_height: TerminalHeight {
+                agl_m: 0.0,
+                amsl_m: 0.0,
+            },
+            end_height: TerminalHeight {
+                agl_m: 0.0,
+                amsl_m: 0.0,
+            },
+            clutter: Some(ProfileClutter {
+                classes: vec![10, 255].into(),
+                surface_elev_m: vec![0.0, 0.0].into(),
+            }),
+            footprints: None,
+        };
+        let mut buf = Vec::new();
+        profile.encode(&mut buf).unwrap();
+        assert_eq!(
+            Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap(),
+            profile
+        );
+
+        // The last class is the final value of the penultimate
+        // column.
+        let class_offset = buf.len() - 4 * 2 - 4;
+        for class in [256.0_f32, -1.0, 1.5, f32::NAN] {
+            buf[class_offset..class_offset + 4].copy_from_slice(&class.to_le_bytes());
+            let err = Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{class}");
+        }
+
+        profile.clutter = None;
+        profile.footprints = Some(ProfileFootprints {
+            ids: vec![Some(0x0001_0002_0003_0004), None].into(),
+            heights_m: vec![10.0, 0.0].into(),
+            surface_elev_m: vec![10.0, 0.0].into(),
+        });
+        let mut buf = Vec::new();
+        profile.encode(&mut buf).unwrap();
+        assert_eq!(
+            Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap(),
+            profile
+        );
+
+        // The second id is missing, so marking only its last chunk
+        // present leaves it neither missing nor valid.
+        let chunk_offset = buf.len() - 4 * 2 * 3 + 4;
+        buf[chunk_offset..chunk_offset + 4].copy_from_slice(&7.0_f32.to_le_bytes());
+        let err = Profile::<f32>::decode(&mut Cursor::new(&buf)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
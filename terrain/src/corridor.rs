@@ -0,0 +1,260 @@
+//! Cross-track terrain sampling along a profile.
+
+use crate::{math::linspace, Profile, TerrainError, Tiles};
+use geo::{
+    algorithm::{HaversineBearing, HaversineDestination},
+    geometry::Coord,
+    CoordFloat,
+};
+use num_traits::FromPrimitive;
+
+/// Terrain sampled across a swath centered on a [`Profile`]'s great
+/// circle path.
+///
+/// Each step of the profile is a row, and each cross-track offset a
+/// column.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corridor<C: CoordFloat = f32> {
+    /// Perpendicular distance of each column from the path, negative
+    /// to the left and positive to the right of the direction of
+    /// travel.
+    ///
+    /// Offsets are symmetric, so the middle column is the path
+    /// itself.
+    pub offsets_m: Box<[C]>,
+
+    /// Elevation at every step and offset, row major.
+    pub elevations_m: Box<[C]>,
+
+    /// Highest elevation across the swath at each step.
+    pub max_elev_m: Box<[C]>,
+
+    /// Mean elevation across the swath at each step.
+    pub mean_elev_m: Box<[C]>,
+}
+
+impl<C> Corridor<C>
+where
+    C: CoordFloat,
+{
+    /// Returns the number of steps (rows).
+    pub fn steps(&self) -> usize {
+        self.max_elev_m.len()
+    }
+
+    /// Returns the cross-track elevations at `step`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is out of bounds.
+    pub fn row(&self, step: usize) -> &[C] {
+        let cols = self.offsets_m.len();
+        &self.elevations_m[step * cols..(step + 1) * cols]
+    }
+
+    /// Returns the greatest height by which terrain at `step`
+    /// intrudes into a circle of `radius_m`, perpendicular to the
+    /// path and centered at `los_elev_m`.
+    ///
+    /// With the first Fresnel zone's radius, this evaluates the
+    /// zone's cross-section as a disc rather than a single point
+    /// beneath the LOS. Negative values are clearance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is out of bounds.
+    pub fn intrusion_m(&self, step: usize, los_elev_m: C, radius_m: C) -> C {
+        self.offsets_m
+            .iter()
+            .zip(self.row(step))
+            .filter(|(offset_m, _)| offset_m.abs() <= radius_m)
+            .map(|(&offset_m, &elev_m)| {
+                let lower_m = los_elev_m - (radius_m * radius_m - offset_m * offset_m).sqrt();
+                elev_m - lower_m
+            })
+            .fold(C::neg_infinity(), C::max)
+    }
+}
+
+/// Corridor sampling.
+impl<C> Profile<C>
+where
+    C: CoordFloat + FromPrimitive,
+    f64: From<C>,
+{
+    /// Samples terrain up to `half_width_m` to either side of this
+    /// profile's path, at each of its steps.
+    ///
+    /// Columns are spaced at most `max_cross_step_m` apart. Use the
+    /// same `tiles` this profile was built from, so the middle
+    /// column matches `terrain_elev_m`.
+    ///
+    /// Returns [`TerrainError::NotPositive`] unless both
+    /// `half_width_m` and `max_cross_step_m` are positive and finite.
+    pub fn corridor(
+        &self,
+        tiles: &Tiles,
+        half_width_m: C,
+        max_cross_step_m: C,
+    ) -> Result<Corridor<C>, TerrainError> {
+        for (name, value) in [
+            ("half_width", half_width_m),
+            ("max_cross_step", max_cross_step_m),
+        ] {
+            if !value.is_finite() || value <= C::zero() {
+                return Err(TerrainError::NotPositive {
+                    name,
+                    value: value.into(),
+                });
+            }
+        }
+        // Both are positive, so there is at least one column to
+        // either side of the path.
+        let half_cols = (half_width_m / max_cross_step_m).ceil().to_usize().unwrap();
+        let offsets_m: Box<[C]> =
+            linspace(-half_width_m, half_width_m, 2 * half_cols + 1).collect();
+
+        let (steps, cols) = (self.great_circle.len(), offsets_m.len());
+        let mut elevations_m = Vec::with_capacity(steps * cols);
+        let mut max_elev_m = Vec::with_capacity(steps);
+        let mut mean_elev_m = Vec::with_capacity(steps);
+        let mut tile = tiles.get(Coord {
+            x: self.great_circle[0].x().into(),
+            y: self.great_circle[0].y().into(),
+        })?;
+        let right_angle = C::from(90.0).unwrap();
+        let cols_c = C::from(cols).unwrap();
+
+        for (step, center) in self.great_circle.iter().enumerate() {
+            // Direction of travel through this step.
+            let prev = self.great_circle[step.saturating_sub(1)];
+            let next = self.great_circle[(step + 1).min(steps - 1)];
+            let right = prev.haversine_bearing(next) + right_angle;
+
+            let row_start = elevations_m.len();
+            for &offset_m in &*offsets_m {
+                let point = if offset_m.is_zero() {
+                    *center
+                } else {
+                    center.haversine_destination(right, offset_m)
+                };
+                let coord = Coord {
+                    x: point.x().into(),
+                    y: point.y().into(),
+                };
+                elevations_m.push(C::from(tiles.sample_m(&mut tile, coord)?).unwrap());
+            }
+
+            let row = &elevations_m[row_start..];
+            max_elev_m.push(row.iter().copied().fold(C::neg_infinity(), C::max));
+            mean_elev_m.push(row.iter().copied().fold(C::zero(), |sum, elev| sum + elev) / cols_c);
+        }
+
+        Ok(Corridor {
+            offsets_m,
+            elevations_m: elevations_m.into_boxed_slice(),
+            max_elev_m: max_elev_m.into_boxed_slice(),
+            mean_elev_m: mean_elev_m.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Profile, TerrainError, TileMode, Tiles};
+    use approx::assert_relative_eq;
+    use geo::geometry::Coord;
+
+    #[test]
+    fn test_corridor() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+
+        // West to east, passing ~400 m south of Mt Washington's
+        // summit.
+        let profile = Profile::<f64>::builder()
+            .start(Coord {
+                x: -71.33,
+                y: 44.267,
+            })
+            .max_step(90.0)
+            .end(Coord {
+                x: -71.28,
+                y: 44.267,
+            })
+            .build(&tiles)
+            .unwrap();
+        let corridor = profile.corridor(&tiles, 500.0, 90.0).unwrap();
+
+        assert_eq!(corridor.offsets_m.len(), 13);
+        assert_relative_eq!(corridor.offsets_m[0], -500.0);
+        assert_relative_eq!(corridor.offsets_m[6], 0.0);
+        assert_relative_eq!(corridor.offsets_m[12], 500.0);
+        assert_eq!(corridor.steps(), profile.terrain_elev_m.len());
+        assert_eq!(corridor.elevations_m.len(), corridor.steps() * 13);
+
+        for step in 0..corridor.steps() {
+            let row = corridor.row(step);
+            assert_eq!(row[6], profile.terrain_elev_m[step]);
+            let min = row.iter().copied().fold(f64::INFINITY, f64::min);
+            assert!(corridor.max_elev_m[step] >= row[6]);
+            assert!((min..=corridor.max_elev_m[step]).contains(&corridor.mean_elev_m[step]));
+        }
+
+        // The summit is just off the path, to the left (north).
+        let path_max = profile
+            .terrain_elev_m
+            .iter()
+            .copied()
+            .fold(f64::MIN, f64::max);
+        let (summit_step, swath_max) = corridor
+            .max_elev_m
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f64::MIN), |a, b| if b.1 > a.1 { b } else { a });
+        assert!(swath_max > 1880.0);
+        assert!(path_max < swath_max - 50.0);
+        let summit_col = corridor
+            .row(summit_step)
+            .iter()
+            .position(|elev| *elev == swath_max)
+            .unwrap();
+        assert!(corridor.offsets_m[summit_col] < -250.0);
+
+        for (half_width_m, max_cross_step_m) in [
+            (0.0, 90.0),
+            (-500.0, 90.0),
+            (f64::NAN, 90.0),
+            (500.0, 0.0),
+            (500.0, f64::INFINITY),
+        ] {
+            assert!(matches!(
+                profile.corridor(&tiles, half_width_m, max_cross_step_m),
+                Err(TerrainError::NotPositive { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_intrusion() {
+        // Null Island has no tile, so terrain is flat at 0 m.
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let profile = Profile::<f64>::builder()
+            .start(Coord { x: 0.0, y: 0.0 })
+            .max_step(90.0)
+            .end(Coord { x: 0.01, y: 0.0 })
+            .build(&tiles)
+            .unwrap();
+        let corridor = profile.corridor(&tiles, 30.0, 10.0).unwrap();
+        assert!(corridor.elevations_m.iter().all(|elev| *elev == 0.0));
+
+        // The disc's lowest point is directly below its center.
+        assert_relative_eq!(corridor.intrusion_m(3, 10.0, 20.0), 10.0);
+        assert_relative_eq!(corridor.intrusion_m(3, 25.0, 20.0), -5.0);
+
+        // A swath narrower than a step still samples either side.
+        let narrow = profile.corridor(&tiles, 5.0, 10.0).unwrap();
+        assert_eq!(&*narrow.offsets_m, &[-5.0, 0.0, 5.0]);
+    }
+}
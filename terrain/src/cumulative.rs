@@ -0,0 +1,415 @@
+//! Visibility from, and between, many sites.
+
+use crate::{
+    constants::MEAN_EARTH_RADIUS,
+    tiles::C,
+    viewshed::{Grid, ARCSEC_PER_DEG},
+    Profile, TerrainError, Tiles, Viewshed,
+};
+use geo::geometry::Coord;
+use rayon::prelude::*;
+
+/// A candidate antenna location.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Site {
+    /// Location of the site.
+    pub coord: Coord<C>,
+
+    /// Antenna altitude above ground (meters).
+    pub alt_m: C,
+}
+
+/// How many sites see each DEM cell, and which sites see each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CumulativeViewshed {
+    /// Grid `counts` is laid out on.
+    grid: Grid,
+
+    /// Number of sites that can see each cell, row major starting
+    /// with the southernmost row.
+    counts: Box<[u32]>,
+
+    /// Number of sites.
+    sites: usize,
+
+    /// Row-major `sites` × `sites` matrix of LOS clearance margins.
+    intervisibility: Box<[Option<C>]>,
+}
+
+impl CumulativeViewshed {
+    pub fn builder() -> CumulativeViewshedBuilder {
+        CumulativeViewshedBuilder {
+            sites: Vec::new(),
+            target_alt_m: 0.0,
+            radius_m: None,
+            max_step_m: None,
+            earth_curve: false,
+            earth_radius: MEAN_EARTH_RADIUS,
+        }
+    }
+
+    /// Returns the number of (columns, rows) in this raster.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.grid.cols, self.grid.rows)
+    }
+
+    /// Returns the _center_ of the southwest most cell.
+    pub fn sw_corner_center(&self) -> Coord<C> {
+        self.grid.sw_corner_center
+    }
+
+    /// Returns the width and height of each cell in degrees.
+    pub fn cell_size_deg(&self) -> C {
+        self.grid.cell_deg
+    }
+
+    /// Returns the number of sites that can see each cell, row major
+    /// starting with the southernmost row.
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    /// Returns how many sites can see the cell containing `coord`, or
+    /// `None` if `coord` is outside the raster.
+    pub fn get(&self, coord: Coord<C>) -> Option<u32> {
+        self.grid
+            .coord_to_xy(coord)
+            .map(|xy| self.counts[self.grid.xy_to_linear_index(xy)])
+    }
+
+    /// Returns the number of sites.
+    pub fn sites(&self) -> usize {
+        self.sites
+    }
+
+    /// Returns the LOS clearance margin (meters) between sites `a`
+    /// and `b` if they can see each other.
+    ///
+    /// The margin is the smallest distance between the line of sight
+    /// and the terrain below it. A site is not considered to see
+    /// itself.
+    pub fn intervisibility(&self, a: usize, b: usize) -> Option<C> {
+        assert!(a < self.sites && b < self.sites, "site index out of range");
+        self.intervisibility[a * self.sites + b]
+    }
+
+    /// Returns the row-major `sites` × `sites` intervisibility
+    /// matrix.
+    ///
+    /// See [`CumulativeViewshed::intervisibility`].
+    pub fn intervisibility_matrix(&self) -> &[Option<C>] {
+        &self.intervisibility
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CumulativeViewshedBuilder {
+    /// Candidate sites (at least one required).
+    sites: Vec<Site>,
+
+    /// Target altitude above ground (meters, defaults to 0).
+    target_alt_m: C,
+
+    /// Radius around each site to compute visibility for (required).
+    radius_m: Option<C>,
+
+    /// Maximum distance between points when checking
+    /// intervisibility (required).
+    max_step_m: Option<C>,
+
+    /// Add earth curvature (defaults to false).
+    earth_curve: bool,
+
+    /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    earth_radius: C,
+}
+
+impl CumulativeViewshedBuilder {
+    /// Add a candidate site (at least one required).
+    #[must_use]
+    pub fn site(mut self, site: Site) -> Self {
+        self.sites.push(site);
+        self
+    }
+
+    /// Add candidate sites (at least one required).
+    #[must_use]
+    pub fn sites<I: IntoIterator<Item = Site>>(mut self, sites: I) -> Self {
+        self.sites.extend(sites);
+        self
+    }
+
+    /// Target altitude above ground (meters, defaults to 0).
+    #[must_use]
+    pub fn target_alt(mut self, meters: C) -> Self {
+        self.target_alt_m = meters;
+        self
+    }
+
+    /// Radius around each site to compute visibility for (required).
+    #[must_use]
+    pub fn radius(mut self, meters: C) -> Self {
+        self.radius_m = Some(meters);
+        self
+    }
+
+    /// Maximum distance between points when checking
+    /// intervisibility (required).
+    #[must_use]
+    pub fn max_step(mut self, meters: C) -> Self {
+        self.max_step_m = Some(meters);
+        self
+    }
+
+    /// Add earth curvature (defaults to false).
+    #[must_use]
+    pub fn earth_curve(mut self, add_curve: bool) -> Self {
+        self.earth_curve = add_curve;
+        self
+    }
+
+    /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    #[must_use]
+    pub fn earth_radius(mut self, earth_radius_m: C) -> Self {
+        self.earth_radius = earth_radius_m;
+        self
+    }
+
+    pub fn build(&self, tiles: &Tiles) -> Result<CumulativeViewshed, TerrainError> {
+        let first = self.sites.first().ok_or(TerrainError::Builder("site"))?;
+        let radius_m = self.radius_m.ok_or(TerrainError::Builder("radius"))?;
+        let max_step_m = self.max_step_m.ok_or(TerrainError::Builder("max_step"))?;
+
+        // Sites are aligned on a common grid, but each only samples
+        // and sums the window within `radius_m` of itself, so the
+        // space between distant sites costs nothing beyond its
+        // counts.
+        let cell_deg = C::from(tiles.get(first.coord)?.resolution()) / ARCSEC_PER_DEG;
+        let windows = self
+            .sites
+            .par_iter()
+            .map(|site| {
+                let window = Grid::around(site.coord, radius_m, cell_deg)?;
+                let elevation = window.elevations(tiles)?;
+                let visible = Viewshed::builder()
+                    .center(site.coord)
+                    .alt(site.alt_m)
+                    .target_alt(self.target_alt_m)
+                    .radius(radius_m)
+                    .earth_curve(self.earth_curve)
+                    .earth_radius(self.earth_radius)
+                    .r2(&window, &elevation, tiles)?;
+                Ok((window, visible))
+            })
+            .collect::<Result<Vec<_>, TerrainError>>()?;
+
+        let grid = windows
+            .iter()
+            .map(|(window, _)| window.clone())
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        let mut counts = vec![0_u32; grid.cols * grid.rows].into_boxed_slice();
+        for (window, visible) in &windows {
+            let ((offset_x, offset_y), _) = grid.window(window);
+            for (idx, visible) in visible.iter().enumerate() {
+                let (x, y) = window.linear_index_to_xy(idx);
+                counts[grid.xy_to_linear_index((offset_x + x, offset_y + y))] +=
+                    u32::from(*visible);
+            }
+        }
+
+        let intervisibility = self.intervisibility(tiles, max_step_m)?;
+
+        Ok(CumulativeViewshed {
+            grid,
+            counts,
+            sites: self.sites.len(),
+            intervisibility,
+        })
+    }
+}
+
+/// Private API.
+impl CumulativeViewshedBuilder {
+    /// Returns the LOS clearance margin between every pair of sites.
+    fn intervisibility(
+        &self,
+        tiles: &Tiles,
+        max_step_m: C,
+    ) -> Result<Box<[Option<C>]>, TerrainError> {
+        let n = self.sites.len();
+        let pairs: Vec<(usize, usize)> = (0..n)
+            .flat_map(|a| ((a + 1)..n).map(move |b| (a, b)))
+            .collect();
+
+        let margins = pairs
+            .par_iter()
+            .map(|&(a, b)| {
+                let (a, b) = (self.sites[a], self.sites[b]);
+                Profile::builder()
+                    .start(a.coord)
+                    .start_alt(a.alt_m)
+                    .max_step(max_step_m)
+                    .end(b.coord)
+                    .end_alt(b.alt_m)
+                    .earth_curve(self.earth_curve)
+                    .earth_radius(self.earth_radius)
+                    .build(tiles)
+                    .map(|profile| {
+                        // Without interior samples, the only terrain
+                        // under LOS is at the sites themselves.
+                        profile
+                            .obstructions()
+                            .min_clearance
+                            .map_or(a.alt_m.min(b.alt_m), |obstacle| -obstacle.excess_m)
+                    })
+            })
+            .collect::<Result<Vec<C>, TerrainError>>()?;
+
+        let mut matrix = vec![None; n * n].into_boxed_slice();
+        for (&(a, b), &margin_m) in pairs.iter().zip(margins.iter()) {
+            if margin_m >= 0.0 {
+                matrix[a * n + b] = Some(margin_m);
+                matrix[b * n + a] = Some(margin_m);
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CumulativeViewshed, Site};
+    use crate::{TileMode, Tiles, Viewshed};
+    use geo::geometry::Coord;
+
+    const MT_WASHINGTON: Coord = Coord {
+        y: 44.2705,
+        x: -71.30325,
+    };
+
+    /// Pinkham Notch, in the valley east of Mt Washington.
+    const PINKHAM_NOTCH: Coord = Coord {
+        y: 44.2572,
+        x: -71.2531,
+    };
+
+    /// Crawford Notch, in the valley south-west of Mt Washington.
+    const CRAWFORD_NOTCH: Coord = Coord {
+        y: 44.2181,
+        x: -71.4112,
+    };
+
+    #[test]
+    fn test_single_site_matches_viewshed() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let site = Site {
+            coord: PINKHAM_NOTCH,
+            alt_m: 2.0,
+        };
+        let cumulative = CumulativeViewshed::builder()
+            .site(site)
+            .radius(3_000.0)
+            .max_step(90.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap();
+        let viewshed = Viewshed::builder()
+            .center(site.coord)
+            .alt(site.alt_m)
+            .radius(3_000.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap();
+        assert_eq!(cumulative.dimensions(), viewshed.dimensions());
+        assert_eq!(cumulative.sw_corner_center(), viewshed.sw_corner_center());
+        assert!(cumulative
+            .counts()
+            .iter()
+            .zip(viewshed.mask())
+            .all(|(count, visible)| *count == u32::from(*visible)));
+        assert_eq!(cumulative.intervisibility_matrix(), &[None]);
+    }
+
+    #[test]
+    fn test_intervisibility() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        // The 3-arcsecond data is smeared around the summit, so it
+        // needs a taller mast to clear the neighboring samples.
+        let sites = [
+            (MT_WASHINGTON, 30.0),
+            (PINKHAM_NOTCH, 10.0),
+            (CRAWFORD_NOTCH, 10.0),
+        ]
+        .map(|(coord, alt_m)| Site { coord, alt_m });
+        let cumulative = CumulativeViewshed::builder()
+            .sites(sites)
+            .radius(3_000.0)
+            .max_step(90.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap();
+
+        assert_eq!(cumulative.sites(), 3);
+        for (idx, site) in sites.iter().enumerate() {
+            assert_eq!(cumulative.intervisibility(idx, idx), None);
+            assert_eq!(cumulative.get(site.coord).map(|n| n > 0), Some(true));
+        }
+
+        // The summit can see down into Pinkham Notch, but Crawford
+        // Notch is hidden from both by intervening ridges.
+        let margin = cumulative.intervisibility(0, 1).unwrap();
+        assert!(margin >= 0.0);
+        assert_eq!(cumulative.intervisibility(1, 0), Some(margin));
+        assert_eq!(cumulative.intervisibility(0, 2), None);
+        assert_eq!(cumulative.intervisibility(1, 2), None);
+
+        let max_count = cumulative.counts().iter().max().copied().unwrap();
+        assert!(max_count >= 2);
+        assert!(max_count <= 3);
+    }
+
+    #[test]
+    fn test_counts_sum_viewsheds() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let sites = [PINKHAM_NOTCH, CRAWFORD_NOTCH].map(|coord| Site { coord, alt_m: 10.0 });
+        let cumulative = CumulativeViewshed::builder()
+            .sites(sites)
+            .radius(2_000.0)
+            .max_step(90.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap();
+        let viewsheds = sites.map(|site| {
+            Viewshed::builder()
+                .center(site.coord)
+                .alt(site.alt_m)
+                .radius(2_000.0)
+                .earth_curve(true)
+                .build(&tiles)
+                .unwrap()
+        });
+
+        // The windows are far enough apart for cells between them to
+        // be outside both.
+        let (cols, rows) = cumulative.dimensions();
+        let sw = cumulative.sw_corner_center();
+        let cell_deg = cumulative.cell_size_deg();
+        let mut outside_both = 0;
+        for (idx, count) in cumulative.counts().iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let coord = Coord {
+                x: sw.x + (idx % cols) as f64 * cell_deg,
+                y: sw.y + (idx / cols) as f64 * cell_deg,
+            };
+            let expected: u32 = viewsheds
+                .iter()
+                .map(|viewshed| u32::from(viewshed.get(coord) == Some(true)))
+                .sum();
+            assert_eq!(*count, expected, "{coord:?}");
+            outside_both += usize::from(viewsheds.iter().all(|v| v.get(coord).is_none()));
+        }
+        assert_eq!(cumulative.counts().len(), cols * rows);
+        assert!(outside_both > 0);
+    }
+}
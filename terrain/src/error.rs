@@ -1,3 +1,4 @@
+use geo::geometry::Coord;
 use nasadem::NasademError;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -10,9 +11,37 @@ pub enum TerrainError {
     #[error("{0}")]
     Io(#[from] std::io::Error),
 
+    #[error("{end} altitude {amsl_m} m AMSL is below ground elevation {ground_m} m")]
+    BelowGround {
+        end: &'static str,
+        amsl_m: f64,
+        ground_m: f64,
+    },
+
     #[error("no height files in {0}")]
     Path(PathBuf),
 
     #[error("{0}")]
     Nasadem(#[from] NasademError),
+
+    #[error("{0}")]
+    Tiff(#[from] tiff::TiffError),
+
+    #[error("unsupported clutter raster: {0}")]
+    Clutter(&'static str),
+
+    #[error("ellipsoidal heights require a geoid")]
+    NoGeoid,
+
+    #[error("{0:?} is outside the geoid grid")]
+    OutsideGeoid(Coord<f64>),
+
+    #[error("region does not overlap any samples")]
+    EmptyRegion,
+
+    #[error("'{name}' must be positive and finite, got {value}")]
+    NotPositive { name: &'static str, value: f64 },
+
+    #[error("{radius_m} m around {center:?} spans more than 360° of longitude")]
+    NearPole { center: Coord<f64>, radius_m: f64 },
 }
@@ -0,0 +1,332 @@
+//! Building and structure footprints.
+
+use crate::tiles::C;
+use geo::{
+    algorithm::{BoundingRect, Contains, Intersects},
+    geometry::{Coord, Line, Polygon},
+};
+use rstar::{RTree, RTreeObject, AABB};
+
+/// A building or other structure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footprint {
+    /// Caller-defined identifier, e.g. an OSM way id.
+    pub id: u64,
+
+    /// Outline in longitude/latitude.
+    pub polygon: Polygon<C>,
+
+    /// Height of the structure above ground (meters).
+    pub height_m: f32,
+}
+
+/// An R-tree of [`Footprint`]s.
+#[derive(Debug)]
+pub struct Footprints {
+    rtree: RTree<Indexed>,
+}
+
+/// A footprint with its cached bounding box.
+#[derive(Debug)]
+struct Indexed {
+    envelope: AABB<[C; 2]>,
+    footprint: Footprint,
+}
+
+impl RTreeObject for Indexed {
+    type Envelope = AABB<[C; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+impl Footprints {
+    /// Indexes `footprints`.
+    ///
+    /// Footprints with empty outlines are dropped.
+    pub fn new(footprints: Vec<Footprint>) -> Self {
+        let indexed = footprints
+            .into_iter()
+            .filter_map(|footprint| {
+                let rect = footprint.polygon.bounding_rect()?;
+                Some(Indexed {
+                    envelope: AABB::from_corners(rect.min().into(), rect.max().into()),
+                    footprint,
+                })
+            })
+            .collect();
+        Self {
+            rtree: RTree::bulk_load(indexed),
+        }
+    }
+
+    /// Returns the number of indexed footprints.
+    pub fn len(&self) -> usize {
+        self.rtree.size()
+    }
+
+    /// Returns `true` if there are no footprints.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the tallest footprint containing `coord`.
+    pub fn at(&self, coord: Coord<C>) -> Option<&Footprint> {
+        self.rtree
+            .locate_in_envelope_intersecting(&AABB::from_point(coord.into()))
+            .map(|indexed| &indexed.footprint)
+            .filter(|footprint| footprint.polygon.contains(&coord))
+            .max_by(|a, b| a.height_m.total_cmp(&b.height_m))
+    }
+
+    /// Returns an iterator over footprints intersecting `line`.
+    pub fn intersecting(&self, line: Line<C>) -> impl Iterator<Item = &Footprint> {
+        self.rtree
+            .locate_in_envelope_intersecting(&AABB::from_corners(
+                line.start.into(),
+                line.end.into(),
+            ))
+            .map(|indexed| &indexed.footprint)
+            .filter(move |footprint| footprint.polygon.intersects(&line))
+    }
+}
+
+impl FromIterator<Footprint> for Footprints {
+    fn from_iter<I: IntoIterator<Item = Footprint>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "geojson")]
+impl Footprints {
+    /// Reads polygon and multipolygon features from `geojson`, such
+    /// as an export of OSM buildings.
+    ///
+    /// Heights are read from the numeric, or numeric string (e.g.,
+    /// `"12"` or `"12 m"`), property named `height_key`. Features
+    /// without a height or polygonal geometry are skipped.
+    ///
+    /// Ids are taken from each feature's numeric id, or the trailing
+    /// number of a string id like `"way/123"`, falling back to the
+    /// feature's index. All polygons of a multipolygon share an id.
+    pub fn from_geojson(geojson: &geojson::GeoJson, height_key: &str) -> Self {
+        let features: &[geojson::Feature] = match geojson {
+            geojson::GeoJson::FeatureCollection(collection) => &collection.features,
+            geojson::GeoJson::Feature(feature) => std::slice::from_ref(feature),
+            geojson::GeoJson::Geometry(_) => &[],
+        };
+        features
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, feature)| {
+                let height_m = parse_height(feature.property(height_key)?)?;
+                let id = match &feature.id {
+                    Some(geojson::feature::Id::Number(number)) => number.as_u64(),
+                    Some(geojson::feature::Id::String(string)) => {
+                        string.rsplit('/').next().and_then(|tail| tail.parse().ok())
+                    }
+                    None => None,
+                }
+                .unwrap_or(idx as u64);
+                let polygons =
+                    match geo::Geometry::<C>::try_from(&feature.geometry.as_ref()?.value).ok()? {
+                        geo::Geometry::Polygon(polygon) => vec![polygon],
+                        geo::Geometry::MultiPolygon(multi) => multi.0,
+                        _ => return None,
+                    };
+                Some(polygons.into_iter().map(move |polygon| Footprint {
+                    id,
+                    polygon,
+                    height_m,
+                }))
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+/// Parses a GeoJSON height property.
+#[cfg(feature = "geojson")]
+fn parse_height(value: &geojson::JsonValue) -> Option<f32> {
+    #[allow(clippy::cast_possible_truncation)]
+    match value {
+        geojson::JsonValue::Number(number) => number.as_f64().map(|height| height as f32),
+        geojson::JsonValue::String(string) => {
+            string.trim().trim_end_matches('m').trim().parse().ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Footprint, Footprints};
+    use crate::{Profile, TileMode, Tiles};
+    use geo::{
+        geometry::{Coord, Line},
+        polygon,
+    };
+    use std::sync::Arc;
+
+    /// Returns a square footprint centered on `center`, `size_deg`
+    /// on a side.
+    fn square(id: u64, center: Coord, size_deg: f64, height_m: f32) -> Footprint {
+        let half = size_deg / 2.0;
+        let (x, y) = center.x_y();
+        Footprint {
+            id,
+            polygon: polygon![
+                (x: x - half, y: y - half),
+                (x: x + half, y: y - half),
+                (x: x + half, y: y + half),
+                (x: x - half, y: y + half),
+            ],
+            height_m,
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let footprints: Footprints = [
+            square(1, Coord { x: 0.0, y: 0.0 }, 0.002, 10.0),
+            // A tower rising out of the middle of building 1.
+            square(2, Coord { x: 0.0, y: 0.0 }, 0.0005, 40.0),
+            square(3, Coord { x: 0.01, y: 0.0 }, 0.002, 5.0),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(footprints.len(), 3);
+
+        assert_eq!(footprints.at(Coord { x: 0.0, y: 0.0 }).unwrap().id, 2);
+        assert_eq!(footprints.at(Coord { x: 0.0007, y: 0.0 }).unwrap().id, 1);
+        assert_eq!(footprints.at(Coord { x: 0.01, y: 0.0 }).unwrap().id, 3);
+        assert!(footprints.at(Coord { x: 0.005, y: 0.0 }).is_none());
+
+        let line = Line::new(
+            Coord {
+                x: -0.01,
+                y: 0.0005,
+            },
+            Coord { x: 0.02, y: 0.0005 },
+        );
+        let mut ids: Vec<u64> = footprints.intersecting(line).map(|fp| fp.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, [1, 3]);
+    }
+
+    #[test]
+    fn test_profile_footprints() {
+        // Null Island has no tile, so terrain is flat at 0 m.
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let footprints = Arc::new(Footprints::new(vec![
+            // Much wider than a step.
+            square(7, Coord { x: 0.003, y: 0.0 }, 0.002, 12.0),
+            // Much narrower than a step, so no sample lands inside.
+            square(8, Coord { x: 0.00725, y: 0.0 }, 0.000_05, 30.0),
+        ]));
+
+        let profile = Profile::builder()
+            .start(Coord { x: 0.0, y: 0.0 })
+            .max_step(50.0)
+            .end(Coord { x: 0.01, y: 0.0 })
+            .footprints(footprints)
+            .build(&tiles)
+            .unwrap();
+        let profile_footprints = profile.footprints.as_ref().unwrap();
+
+        let wide: Vec<usize> = profile_footprints
+            .ids
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, id)| (*id == Some(7)).then_some(idx))
+            .collect();
+        // 0.002° is ~220 m, so ~4 samples at 50 m steps.
+        assert!((4..=5).contains(&wide.len()));
+        for idx in wide {
+            let x = profile.great_circle[idx].x();
+            assert!((0.002..=0.004).contains(&x));
+            assert_eq!(profile_footprints.heights_m[idx], 12.0);
+        }
+
+        let narrow: Vec<usize> = profile_footprints
+            .ids
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, id)| (*id == Some(8)).then_some(idx))
+            .collect();
+        assert_eq!(narrow.len(), 1);
+        assert_eq!(profile_footprints.heights_m[narrow[0]], 30.0);
+
+        for ((id, height_m), (surface_m, terrain_m)) in profile_footprints
+            .ids
+            .iter()
+            .zip(profile_footprints.heights_m.iter())
+            .zip(
+                profile_footprints
+                    .surface_elev_m
+                    .iter()
+                    .zip(profile.terrain_elev_m.iter()),
+            )
+        {
+            assert_eq!(id.is_none(), *height_m == 0.0);
+            assert_eq!(*surface_m, terrain_m + height_m);
+        }
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn test_from_geojson() {
+        let geojson: geojson::GeoJson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "id": "way/123",
+                    "properties": { "building": "yes", "height": "12.5 m" },
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "id": 456,
+                    "properties": { "height": 20 },
+                    "geometry": {
+                        "type": "MultiPolygon",
+                        "coordinates": [
+                            [[[2, 0], [3, 0], [3, 1], [2, 1], [2, 0]]],
+                            [[[4, 0], [5, 0], [5, 1], [4, 1], [4, 0]]]
+                        ]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "properties": { "building": "yes" },
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[6, 0], [7, 0], [7, 1], [6, 1], [6, 0]]]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "properties": { "height": 3 },
+                    "geometry": { "type": "Point", "coordinates": [8, 0.5] }
+                }
+            ]
+        }"#
+        .parse()
+        .unwrap();
+        let footprints = Footprints::from_geojson(&geojson, "height");
+        assert_eq!(footprints.len(), 3);
+
+        let first = footprints.at(Coord { x: 0.5, y: 0.5 }).unwrap();
+        assert_eq!((first.id, first.height_m), (123, 12.5));
+        let second = footprints.at(Coord { x: 2.5, y: 0.5 }).unwrap();
+        let third = footprints.at(Coord { x: 4.5, y: 0.5 }).unwrap();
+        assert_eq!((second.id, second.height_m), (456, 20.0));
+        assert_eq!((third.id, third.height_m), (456, 20.0));
+        assert!(footprints.at(Coord { x: 6.5, y: 0.5 }).is_none());
+    }
+}
@@ -0,0 +1,414 @@
+//! Geoid undulation grids.
+
+use crate::{tiles::C, TerrainError};
+use byteorder::{BigEndian as BE, ByteOrder, ReadBytesExt};
+use geo::geometry::Coord;
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+/// A grid of geoid heights above the WGS84 ellipsoid (undulations),
+/// such as EGM96 or EGM2008.
+///
+/// NASADEM elevations are orthometric (relative to the EGM96 geoid),
+/// while GPS reports heights above the ellipsoid. The two differ by
+/// the undulation `N`: `ellipsoidal = orthometric + N`.
+///
+/// Grids are read from the GTX format used by PROJ (e.g.,
+/// `egm96_15.gtx` or `egm08_25.gtx`).
+pub struct Geoid {
+    /// Coordinate of the south-west grid node.
+    sw_node: Coord<C>,
+
+    /// Distance between grid nodes in degrees.
+    spacing_deg: Coord<C>,
+
+    /// Number of nodes along a parallel.
+    cols: usize,
+
+    /// Number of nodes along a meridian.
+    rows: usize,
+
+    /// Whether the grid wraps around the antimeridian.
+    global: bool,
+
+    /// Undulations, row major starting with the southernmost row.
+    undulations_m: Box<[f32]>,
+}
+
+impl Geoid {
+    /// Reads a GTX grid from disk.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, TerrainError> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let header = Header::read(&mut reader)?;
+        // Reject truncated files before reading any undulations.
+        if HEADER_LEN + header.data_len()? > file_len {
+            return Err(invalid_data("truncated geoid grid").into());
+        }
+        Self::read_undulations(reader, header)
+    }
+
+    /// Reads a GTX grid from `reader`.
+    ///
+    /// The GTX header is 40 big endian bytes: south latitude, west
+    /// longitude, latitude spacing, and longitude spacing as `f64`,
+    /// followed by row and column counts as `i32`. Rows of `f32`
+    /// undulations follow, from south to north.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, TerrainError> {
+        let header = Header::read(&mut reader)?;
+        Self::read_undulations(reader, header)
+    }
+
+    /// Returns the bilinearly interpolated undulation `N` at `coord`,
+    /// or `None` if `coord` is outside this grid.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn undulation_m(&self, coord: Coord<C>) -> Option<C> {
+        let row = (coord.y - self.sw_node.y) / self.spacing_deg.y;
+        let col = if self.global {
+            (coord.x - self.sw_node.x).rem_euclid(360.0) / self.spacing_deg.x
+        } else {
+            (coord.x - self.sw_node.x) / self.spacing_deg.x
+        };
+        let max_row = (self.rows - 1) as C;
+        let max_col = (self.cols - 1) as C;
+        if !(0.0..=max_row).contains(&row) || !(self.global || (0.0..=max_col).contains(&col)) {
+            return None;
+        }
+
+        let row_0 = (row.floor() as usize).min(self.rows - 2);
+        let col_0 = col.floor() as usize;
+        let col_1 = match col_0 + 1 {
+            next if next < self.cols => next,
+            // Wrap around a global grid without a duplicated
+            // antimeridian column.
+            next if self.global => next % self.cols,
+            // On a regional grid's east edge.
+            _ => col_0,
+        };
+        let (dy, dx) = (row - row_0 as C, col - col_0 as C);
+
+        let node = |row: usize, col: usize| C::from(self.undulations_m[row * self.cols + col]);
+        let south = node(row_0, col_0) * (1.0 - dx) + node(row_0, col_1) * dx;
+        let north = node(row_0 + 1, col_0) * (1.0 - dx) + node(row_0 + 1, col_1) * dx;
+        Some(south * (1.0 - dy) + north * dy)
+    }
+
+    /// Converts a height above the geoid at `coord` to a height above
+    /// the ellipsoid.
+    pub fn to_ellipsoidal(&self, coord: Coord<C>, orthometric_m: C) -> Result<C, TerrainError> {
+        Ok(orthometric_m + self.try_undulation_m(coord)?)
+    }
+
+    /// Converts a height above the ellipsoid at `coord` to a height
+    /// above the geoid.
+    pub fn to_orthometric(&self, coord: Coord<C>, ellipsoidal_m: C) -> Result<C, TerrainError> {
+        Ok(ellipsoidal_m - self.try_undulation_m(coord)?)
+    }
+
+    /// Returns the undulation at `coord`, or an error if `coord` is
+    /// outside this grid.
+    pub(crate) fn try_undulation_m(&self, coord: Coord<C>) -> Result<C, TerrainError> {
+        self.undulation_m(coord)
+            .ok_or(TerrainError::OutsideGeoid(coord))
+    }
+}
+
+/// Private API.
+impl Geoid {
+    fn read_undulations<R: Read>(reader: R, header: Header) -> Result<Self, TerrainError> {
+        let Header {
+            sw_node,
+            spacing_deg,
+            rows,
+            cols,
+        } = header;
+
+        // Buffer only as much as the stream actually holds, rather
+        // than trusting the header's dimensions up front.
+        let data_len = header.data_len()?;
+        let mut buf = Vec::new();
+        reader.take(data_len).read_to_end(&mut buf)?;
+        if buf.len() as u64 != data_len {
+            return Err(invalid_data("truncated geoid grid").into());
+        }
+        let undulations_m: Box<[f32]> = buf.chunks_exact(4).map(BE::read_f32).collect();
+
+        // Allow for rounding in the header's spacing.
+        #[allow(clippy::cast_precision_loss)]
+        let global = cols as C * spacing_deg.x >= 360.0 - spacing_deg.x / 2.0;
+
+        Ok(Self {
+            sw_node,
+            spacing_deg,
+            cols,
+            rows,
+            global,
+            undulations_m,
+        })
+    }
+}
+
+/// Length of a GTX header in bytes.
+const HEADER_LEN: u64 = 40;
+
+/// A validated GTX header.
+#[derive(Clone, Copy)]
+struct Header {
+    sw_node: Coord<C>,
+    spacing_deg: Coord<C>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Header {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, TerrainError> {
+        let south = reader.read_f64::<BE>()?;
+        let west = reader.read_f64::<BE>()?;
+        let lat_spacing = reader.read_f64::<BE>()?;
+        let lon_spacing = reader.read_f64::<BE>()?;
+        let rows = reader.read_i32::<BE>()?;
+        let cols = reader.read_i32::<BE>()?;
+        if rows < 2 || cols < 2 {
+            return Err(invalid_data("geoid grid needs at least 2 rows and columns").into());
+        }
+        let positive = |spacing: f64| spacing.is_finite() && spacing > 0.0;
+        if !(south.is_finite()
+            && west.is_finite()
+            && positive(lat_spacing)
+            && positive(lon_spacing))
+        {
+            return Err(invalid_data("degenerate geoid grid").into());
+        }
+        // Both are at least 2, so these can't fail.
+        let (rows, cols) = (
+            usize::try_from(rows).unwrap(),
+            usize::try_from(cols).unwrap(),
+        );
+        let header = Self {
+            sw_node: Coord { x: west, y: south },
+            spacing_deg: Coord {
+                x: lon_spacing,
+                y: lat_spacing,
+            },
+            rows,
+            cols,
+        };
+        header.data_len()?;
+        Ok(header)
+    }
+
+    /// Returns the number of bytes of undulations following the
+    /// header.
+    fn data_len(self) -> Result<u64, TerrainError> {
+        self.rows
+            .checked_mul(self.cols)
+            .and_then(|nodes| u64::try_from(nodes).ok())
+            .and_then(|nodes| nodes.checked_mul(4))
+            .ok_or_else(|| invalid_data("geoid grid is too large").into())
+    }
+}
+
+fn invalid_data(msg: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+impl std::fmt::Debug for Geoid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Geoid")
+            .field("sw_node", &self.sw_node)
+            .field("spacing_deg", &self.spacing_deg)
+            .field("cols", &self.cols)
+            .field("rows", &self.rows)
+            .field("global", &self.global)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Geoid;
+    use crate::{Altitude, Profile, TerrainError, TileMode, Tiles};
+    use approx::assert_relative_eq;
+    use byteorder::{BigEndian as BE, WriteBytesExt};
+    use geo::geometry::Coord;
+    use std::{io::Cursor, sync::Arc};
+
+    /// Returns a GTX grid whose undulation at each node is `f(lon,
+    /// lat)`.
+    fn gtx(
+        sw_node: Coord,
+        spacing_deg: f64,
+        cols: i32,
+        rows: i32,
+        f: fn(f64, f64) -> f64,
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for value in [sw_node.y, sw_node.x, spacing_deg, spacing_deg] {
+            buf.write_f64::<BE>(value).unwrap();
+        }
+        buf.write_i32::<BE>(rows).unwrap();
+        buf.write_i32::<BE>(cols).unwrap();
+        for row in 0..rows {
+            for col in 0..cols {
+                let lon = sw_node.x + f64::from(col) * spacing_deg;
+                let lat = sw_node.y + f64::from(row) * spacing_deg;
+                #[allow(clippy::cast_possible_truncation)]
+                buf.write_f32::<BE>(f(lon, lat) as f32).unwrap();
+            }
+        }
+        buf
+    }
+
+    /// A global 10° grid without a duplicated antimeridian column.
+    fn global() -> Geoid {
+        let buf = gtx(
+            Coord {
+                x: -180.0,
+                y: -90.0,
+            },
+            10.0,
+            36,
+            19,
+            |lon, lat| 20.0 + lat / 2.0 + lon.abs() / 10.0,
+        );
+        Geoid::from_reader(Cursor::new(buf)).unwrap()
+    }
+
+    #[test]
+    fn test_undulation() {
+        let geoid = global();
+
+        // On nodes.
+        assert_relative_eq!(geoid.undulation_m(Coord { x: 0.0, y: 0.0 }).unwrap(), 20.0);
+        assert_relative_eq!(
+            geoid.undulation_m(Coord { x: 20.0, y: 40.0 }).unwrap(),
+            42.0
+        );
+        assert_relative_eq!(geoid.undulation_m(Coord { x: 0.0, y: 90.0 }).unwrap(), 65.0);
+
+        // Between nodes.
+        assert_relative_eq!(
+            geoid.undulation_m(Coord { x: 15.0, y: 45.0 }).unwrap(),
+            44.0,
+            epsilon = 1e-6
+        );
+
+        // Across the antimeridian, 175°E and 175°W both interpolate
+        // between 170°E (37 m) and 180°W (38 m).
+        assert_relative_eq!(
+            geoid.undulation_m(Coord { x: 175.0, y: 0.0 }).unwrap(),
+            37.5,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            geoid.undulation_m(Coord { x: -185.0, y: 0.0 }).unwrap(),
+            37.5,
+            epsilon = 1e-6
+        );
+
+        // Regional grids don't wrap.
+        let regional = Geoid::from_reader(Cursor::new(gtx(
+            Coord { x: -80.0, y: 40.0 },
+            1.0,
+            11,
+            6,
+            |_, _| -30.0,
+        )))
+        .unwrap();
+        assert_relative_eq!(
+            regional.undulation_m(Coord { x: -71.3, y: 44.3 }).unwrap(),
+            -30.0
+        );
+        assert!(regional.undulation_m(Coord { x: -68.0, y: 44.3 }).is_none());
+        assert!(regional.undulation_m(Coord { x: -71.3, y: 46.5 }).is_none());
+        assert!(matches!(
+            regional.to_ellipsoidal(Coord { x: 0.0, y: 0.0 }, 0.0),
+            Err(TerrainError::OutsideGeoid(_))
+        ));
+
+        let coord = Coord { x: -71.3, y: 44.3 };
+        let ellipsoidal_m = regional.to_ellipsoidal(coord, 1900.0).unwrap();
+        assert_relative_eq!(ellipsoidal_m, 1870.0);
+        assert_relative_eq!(
+            regional.to_orthometric(coord, ellipsoidal_m).unwrap(),
+            1900.0
+        );
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let sw_node = Coord { x: 0.0, y: 0.0 };
+        let read = |buf: Vec<u8>| Geoid::from_reader(Cursor::new(buf));
+
+        for (cols, rows) in [(-3, 3), (3, 0), (1, 3)] {
+            assert!(read(gtx(sw_node, 1.0, cols, rows, |_, _| 0.0)).is_err());
+        }
+        assert!(read(gtx(sw_node, 0.0, 3, 3, |_, _| 0.0)).is_err());
+
+        // A header claiming far more nodes than the stream holds.
+        let mut buf = gtx(sw_node, 1.0, 3, 3, |_, _| 0.0);
+        buf[32..40].copy_from_slice(&[0x7f, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff]);
+        assert!(read(buf).is_err());
+
+        // A truncated file.
+        let mut buf = gtx(sw_node, 1.0, 3, 3, |_, _| 0.0);
+        buf.pop();
+        let path =
+            std::env::temp_dir().join(format!("terrain-truncated-{}.gtx", std::process::id()));
+        std::fs::write(&path, buf).unwrap();
+        let result = Geoid::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_ellipsoidal_tiles() {
+        let geoid = Arc::new(global());
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let ellipsoidal = tiles.clone().ellipsoidal(geoid.clone());
+
+        // Null Island has no tile, so orthometric terrain is 0 m and
+        // ellipsoidal terrain the undulation: 20 m, increasing by 0.1
+        // m per degree east.
+        let start = Coord { x: 0.0, y: 0.0 };
+        let end = Coord { x: 1.0, y: 0.0 };
+        assert_relative_eq!(ellipsoidal.elevation_m(end).unwrap(), 20.1, epsilon = 1e-6);
+        assert_relative_eq!(tiles.elevation_m(end).unwrap(), 0.0);
+
+        let builder = Profile::builder()
+            .start(start)
+            .start_altitude(Altitude::Ellipsoidal(50.0))
+            .max_step(1000.0)
+            .end(end)
+            .end_alt_amsl(10.0);
+        let profile = builder.build(&ellipsoidal).unwrap();
+        assert_relative_eq!(profile.terrain_elev_m[0], 20.0);
+        assert_relative_eq!(
+            *profile.terrain_elev_m.last().unwrap(),
+            20.1,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(profile.start_height.amsl_m, 50.0);
+        assert_relative_eq!(profile.start_height.agl_m, 30.0);
+        assert_relative_eq!(profile.end_height.amsl_m, 30.1, epsilon = 1e-6);
+        assert_relative_eq!(profile.end_height.agl_m, 10.0, epsilon = 1e-6);
+
+        // Orthometric tiles need a geoid to resolve ellipsoidal
+        // altitudes.
+        assert!(matches!(builder.build(&tiles), Err(TerrainError::NoGeoid)));
+        let profile = builder.build(&tiles.with_geoid(geoid)).unwrap();
+        assert_relative_eq!(profile.terrain_elev_m[0], 0.0);
+        assert_relative_eq!(*profile.terrain_elev_m.last().unwrap(), 0.0);
+        assert_relative_eq!(profile.start_height.amsl_m, 30.0);
+        assert_relative_eq!(profile.start_height.agl_m, 30.0);
+        assert_relative_eq!(profile.end_height.amsl_m, 10.0);
+    }
+}
@@ -0,0 +1,276 @@
+//! Terrain horizon around a single site.
+
+use crate::{constants::MEAN_EARTH_RADIUS, math::elevation_angle, Profile, TerrainError, Tiles};
+use geo::{
+    algorithm::HaversineDestination,
+    geometry::{Coord, Point},
+    CoordFloat,
+};
+use num_traits::{AsPrimitive, FloatConst, FromPrimitive};
+use rayon::prelude::*;
+
+/// Maximum terrain elevation angle, by azimuth, as seen from a site.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HorizonMask<C: CoordFloat = f32> {
+    /// Azimuth of each spoke, in degrees clockwise from true north.
+    pub azimuths_deg: Box<[C]>,
+
+    /// Highest elevation angle (radians) from the site to any
+    /// terrain sample along the spoke, taking earth curvature into
+    /// account.
+    pub elevation_angles_rad: Box<[C]>,
+
+    /// Distance from the site to the terrain sample defining the
+    /// horizon along each spoke (only when requested).
+    pub obstacle_distances_m: Option<Box<[C]>>,
+}
+
+impl<C> HorizonMask<C>
+where
+    C: CoordFloat,
+{
+    pub fn builder() -> HorizonBuilder<C> {
+        HorizonBuilder {
+            center: None,
+            alt_m: C::zero(),
+            radius_m: None,
+            max_step_m: None,
+            spokes: 360,
+            earth_radius: C::from(MEAN_EARTH_RADIUS).unwrap(),
+            obstacle_distances: false,
+        }
+    }
+
+    /// Returns the number of spokes in this mask.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.azimuths_deg.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HorizonBuilder<C: CoordFloat = f32> {
+    /// Location of the site (required).
+    center: Option<Coord<C>>,
+
+    /// Site altitude above ground (meters, defaults to 0).
+    alt_m: C,
+
+    /// How far out to look for the horizon (required).
+    radius_m: Option<C>,
+
+    /// Maximum distance between points along each spoke (required).
+    max_step_m: Option<C>,
+
+    /// Number of evenly spaced spokes (defaults to 360).
+    spokes: usize,
+
+    /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    earth_radius: C,
+
+    /// Also report distance to the horizon defining sample (defaults
+    /// to false).
+    obstacle_distances: bool,
+}
+
+impl<C> HorizonBuilder<C>
+where
+    C: CoordFloat + FromPrimitive,
+    f64: From<C>,
+{
+    /// Location of the site (required).
+    #[must_use]
+    pub fn center(mut self, coord: Coord<C>) -> Self {
+        self.center = Some(coord);
+        self
+    }
+
+    /// Site altitude above ground (meters, defaults to 0).
+    #[must_use]
+    pub fn alt(mut self, meters: C) -> Self {
+        self.alt_m = meters;
+        self
+    }
+
+    /// How far out to look for the horizon (required).
+    #[must_use]
+    pub fn radius(mut self, meters: C) -> Self {
+        self.radius_m = Some(meters);
+        self
+    }
+
+    /// Maximum distance between points along each spoke (required).
+    #[must_use]
+    pub fn max_step(mut self, meters: C) -> Self {
+        self.max_step_m = Some(meters);
+        self
+    }
+
+    /// Number of evenly spaced spokes, starting at true north
+    /// (defaults to 360).
+    #[must_use]
+    pub fn spokes(mut self, spokes: usize) -> Self {
+        self.spokes = spokes;
+        self
+    }
+
+    /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    ///
+    /// Use an effective earth radius (e.g., 4/3 of mean) to account
+    /// for atmospheric refraction.
+    #[must_use]
+    pub fn earth_radius(mut self, earth_radius_m: C) -> Self {
+        self.earth_radius = earth_radius_m;
+        self
+    }
+
+    /// Also report the distance to each spoke's horizon defining
+    /// sample (defaults to false).
+    #[must_use]
+    pub fn obstacle_distances(mut self, obstacle_distances: bool) -> Self {
+        self.obstacle_distances = obstacle_distances;
+        self
+    }
+
+    pub fn build(&self, tiles: &Tiles) -> Result<HorizonMask<C>, TerrainError>
+    where
+        C: FloatConst + AsPrimitive<usize> + Send + Sync,
+    {
+        let center = self.center.ok_or(TerrainError::Builder("center"))?;
+        let radius_m = self.radius_m.ok_or(TerrainError::Builder("radius"))?;
+        let max_step_m = self.max_step_m.ok_or(TerrainError::Builder("max_step"))?;
+
+        let azimuths_deg: Box<[C]> = (0..self.spokes)
+            .map(|spoke| {
+                C::from(spoke).unwrap() * C::from(360).unwrap() / C::from(self.spokes).unwrap()
+            })
+            .collect();
+
+        let pairs: Vec<(Coord<C>, Coord<C>)> = azimuths_deg
+            .iter()
+            .map(|&azimuth_deg| {
+                let end = Point::from(center).haversine_destination(azimuth_deg, radius_m);
+                (center, end.0)
+            })
+            .collect();
+
+        // We want raw terrain here, as `elevation_angle` accounts for
+        // earth curvature itself.
+        let spokes = Profile::builder()
+            .max_step(max_step_m)
+            .earth_radius(self.earth_radius)
+            .par_build(tiles, &pairs)
+            .map(|profile| profile.map(|profile| self.spoke_horizon(&profile)))
+            .collect::<Result<Vec<(C, C)>, TerrainError>>()?;
+
+        let elevation_angles_rad = spokes.iter().map(|&(angle, _)| angle).collect();
+        let obstacle_distances_m = self
+            .obstacle_distances
+            .then(|| spokes.iter().map(|&(_, distance)| distance).collect());
+
+        Ok(HorizonMask {
+            azimuths_deg,
+            elevation_angles_rad,
+            obstacle_distances_m,
+        })
+    }
+}
+
+/// Private API.
+impl<C> HorizonBuilder<C>
+where
+    C: CoordFloat + FloatConst,
+{
+    /// Returns the highest elevation angle along `profile` and the
+    /// distance to the sample it was found at.
+    fn spoke_horizon(&self, profile: &Profile<C>) -> (C, C) {
+        let site_elev_m = profile.terrain_elev_m[0] + self.alt_m;
+        profile
+            .distances_m
+            .iter()
+            .zip(profile.terrain_elev_m.iter())
+            .skip(1)
+            .map(|(&distance_m, &elev_m)| {
+                let angle = elevation_angle(site_elev_m, distance_m, elev_m, self.earth_radius);
+                (angle, distance_m)
+            })
+            .fold((C::neg_infinity(), C::zero()), |best, candidate| {
+                if candidate.0 > best.0 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HorizonMask;
+    use crate::{TileMode, Tiles};
+    use geo::geometry::Coord;
+
+    const MT_WASHINGTON: Coord = Coord {
+        y: 44.2705,
+        x: -71.30325,
+    };
+
+    /// Pinkham Notch, in the valley east of Mt Washington.
+    const PINKHAM_NOTCH: Coord = Coord {
+        y: 44.2572,
+        x: -71.2531,
+    };
+
+    #[test]
+    fn test_summit_horizon_is_below_level() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        // The 3-arcsecond data is smeared around the summit, so we
+        // need a small mast to clear the neighboring samples.
+        let mask = HorizonMask::builder()
+            .center(MT_WASHINGTON)
+            .alt(10.0)
+            .radius(10_000.0)
+            .max_step(90.0)
+            .spokes(36)
+            .build(&tiles)
+            .unwrap();
+        assert_eq!(mask.len(), 36);
+        assert_eq!(mask.azimuths_deg[9], 90.0);
+        assert!(mask.obstacle_distances_m.is_none());
+        assert!(mask.elevation_angles_rad.iter().all(|angle| *angle < 0.0));
+    }
+
+    #[test]
+    fn test_valley_horizon_faces_summit() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let mask = HorizonMask::builder()
+            .center(PINKHAM_NOTCH)
+            .alt(2.0)
+            .radius(10_000.0)
+            .max_step(90.0)
+            .spokes(8)
+            .obstacle_distances(true)
+            .build(&tiles)
+            .unwrap();
+
+        // The summit is west-north-west of Pinkham Notch and towers
+        // ~1 km above it.
+        let (steepest, angle) = mask.elevation_angles_rad.iter().enumerate().fold(
+            (0, f64::MIN),
+            |best, (idx, &angle)| {
+                if angle > best.1 {
+                    (idx, angle)
+                } else {
+                    best
+                }
+            },
+        );
+        assert!(steepest == 6 || steepest == 7, "{steepest}");
+        assert!(angle.to_degrees() > 10.0, "{}", angle.to_degrees());
+
+        let distances = mask.obstacle_distances_m.unwrap();
+        assert_eq!(distances.len(), 8);
+        assert!(distances.iter().all(|d| *d > 0.0 && *d <= 10_000.0));
+    }
+}
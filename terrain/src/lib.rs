@@ -56,7 +56,6 @@
 //!     .end(end)
 //!     .end_alt(end_alt_m)
 //!     .earth_curve(true)
-//!     .normalize(true)
 //!     .build(&tiles)?;
 //! #
 //! #     Ok(())
@@ -66,25 +65,45 @@
 //! **Output Data**
 //!
 //! Here's an externally generated plot (plotting not included in this
-//! crate) of `profile`'s [`los_elev_m`] and [`terrain_elev_m`] over its
-//! [`distances_m`]:
+//! crate) of `profile`'s [`los_elev_m`] and [`flat_earth_terrain_iter`]
+//! (terrain raised by earth bulge) over its [`distances_m`]:
 //!
 //! ![Lake Tahoe](https://github.com/JayKickliter/geoprof/assets/2551201/b8c94b4b-017c-4dd1-8a87-37c808ccea2b)
 //!
 //! [`los_elev_m`]: struct.Profile.html#structfield.los_elev_m
-//! [`terrain_elev_m`]: struct.Profile.html#structfield.terrain_elev_m
+//! [`flat_earth_terrain_iter`]: struct.Profile.html#method.flat_earth_terrain_iter
 //! [`distances_m`]: struct.Profile.html#structfield.distances_m
 
+mod clutter;
+pub mod codec;
 pub mod constants;
+mod corridor;
+mod cumulative;
 mod error;
+mod footprints;
+mod geoid;
+mod horizon;
 mod math;
 mod profile;
 mod tiles;
+mod viewshed;
+mod zonal;
 
 pub use crate::{
+    clutter::{Clutter, ClutterClass, ClutterRaster, ClutterTable, NO_CLASS},
+    corridor::Corridor,
+    cumulative::{CumulativeViewshed, CumulativeViewshedBuilder, Site},
     error::TerrainError,
-    profile::{Profile, ProfileBuilder},
+    footprints::{Footprint, Footprints},
+    geoid::Geoid,
+    horizon::{HorizonBuilder, HorizonMask},
+    profile::{
+        Altitude, Horizon, Obstacle, Obstructions, Profile, ProfileBuilder, ProfileClutter,
+        ProfileFootprints, ProfileStats, TerminalHeight,
+    },
     tiles::{TileMode, Tiles},
+    viewshed::{Viewshed, ViewshedBuilder},
+    zonal::ZonalStats,
 };
 
 pub use geo;
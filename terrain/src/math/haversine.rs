@@ -23,7 +23,8 @@ impl<T: CoordFloat> HaversineIter<T> {
         let params = get_params(&start, &end);
         let HaversineParams { d, .. } = params;
         let total_distance = d * T::from(MEAN_EARTH_RADIUS).unwrap();
-        let number_of_points = (total_distance / max_step_size).ceil();
+        // A zero-length path still has a start and an end.
+        let number_of_points = (total_distance / max_step_size).ceil().max(T::one());
         let step_size_m = total_distance / number_of_points;
 
         Self {
@@ -94,8 +95,12 @@ where
         s,
     } = *params;
 
-    let a = ((one - f) * d).sin() / d.sin();
-    let b = (f * d).sin() / d.sin();
+    let (a, b) = if d.is_zero() {
+        // Start and end coincide.
+        (one - f, f)
+    } else {
+        (((one - f) * d).sin() / d.sin(), (f * d).sin() / d.sin())
+    };
 
     let x = a * n + b * o;
     let y = a * p + b * q;
@@ -176,4 +181,54 @@ mod tests {
         ];
         assert_eq!(points, expected);
     }
+
+    #[test]
+    fn test_haversine_iter_antimeridian() {
+        let start = point!(x: 179.9_f64, y: 10.0);
+        let end = point!(x: -179.9, y: 10.0);
+        let points: Vec<_> = HaversineIter::new(start, 1_000.0, end).collect();
+
+        // ~22 km the short way, not ~39,000 km the long way around.
+        assert_eq!(points.len(), 23);
+        for point in &points {
+            assert!(point.x().abs() >= 179.9 - 1e-9, "{point:?}");
+            assert_relative_eq!(point.y(), 10.0, epsilon = 1e-4);
+        }
+        let crossing = points.iter().position(|point| point.x() < 0.0).unwrap();
+        assert!(points[..crossing].iter().all(|point| point.x() > 0.0));
+        assert!(points[crossing..].iter().all(|point| point.x() < 0.0));
+        assert_relative_eq!(points[0], start, epsilon = 1e-9);
+        assert_relative_eq!(points[22], end, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_haversine_iter_polar() {
+        // Over the north pole, from one side of the globe to the
+        // other.
+        let start = point!(x: 0.0_f64, y: 89.0);
+        let end = point!(x: 180.0, y: 89.0);
+        let points: Vec<_> = HaversineIter::new(start, 10_000.0, end).collect();
+        assert_eq!(points.len(), 24);
+        let max_lat = points
+            .iter()
+            .map(|point| point.y())
+            .fold(f64::MIN, f64::max);
+        assert!(max_lat > 89.9);
+        assert!(points.iter().all(|point| point.y() <= 90.0));
+        assert!(points[..11].iter().all(|point| point.x().abs() < 1e-9));
+        assert!(points[13..]
+            .iter()
+            .all(|point| (point.x().abs() - 180.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_haversine_iter_zero_length() {
+        let start = point!(x: 180.0_f64, y: -90.0);
+        let points: Vec<_> = HaversineIter::new(start, 90.0, start).collect();
+        assert_eq!(points.len(), 2);
+        for point in points {
+            assert!(!point.x().is_nan() && !point.y().is_nan());
+            assert_relative_eq!(point.y(), -90.0);
+        }
+    }
 }
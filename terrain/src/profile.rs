@@ -1,16 +1,21 @@
 use crate::{
+    clutter::Clutter,
     constants::MEAN_EARTH_RADIUS,
-    math::{elevation_angle, linspace, HaversineIter},
-    TerrainError, Tiles,
+    footprints::Footprints,
+    math::{linspace, HaversineIter},
+    Geoid, TerrainError, Tiles,
 };
 use geo::{
-    algorithm::HaversineDistance,
-    geometry::{Coord, Point},
+    algorithm::{BoundingRect, HaversineDistance},
+    geometry::{Coord, Line, Point},
     CoordFloat,
 };
 use log::debug;
 use num_traits::{AsPrimitive, FloatConst, FromPrimitive};
+use rayon::prelude::*;
+use std::sync::Arc;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Profile<C: CoordFloat = f32> {
     /// Incremental path distance for all following vectors.
@@ -22,10 +27,125 @@ pub struct Profile<C: CoordFloat = f32> {
 
     /// Elevation at each step along the great circle route from
     /// `start` to `end`.
+    ///
+    /// Like all heights in a profile, this is above mean sea level,
+    /// or above the ellipsoid if built from
+    /// [ellipsoidal](Tiles::ellipsoidal) tiles.
     pub terrain_elev_m: Box<[C]>,
 
+    /// Height of the earth's surface above the chord from `start` to
+    /// `end` at each step, for the builder's (possibly effective)
+    /// earth radius.
+    ///
+    /// All zeros unless built with `earth_curve`.
+    pub earth_bulge_m: Box<[C]>,
+
     /// A straight line from `start` to `end`.
     pub los_elev_m: Box<[C]>,
+
+    /// Height of the start of the path.
+    pub start_height: TerminalHeight<C>,
+
+    /// Height of the end of the path.
+    pub end_height: TerminalHeight<C>,
+
+    /// Land cover along the path, if built with a clutter layer.
+    pub clutter: Option<ProfileClutter<C>>,
+
+    /// Structures along the path, if built with footprints.
+    pub footprints: Option<ProfileFootprints<C>>,
+}
+
+/// Derived views.
+impl<C> Profile<C>
+where
+    C: CoordFloat,
+{
+    /// Returns an iterator over terrain raised by earth bulge.
+    ///
+    /// Pair this with the straight `los_elev_m` for the classic flat
+    /// earth plot of a path.
+    pub fn flat_earth_terrain_iter(&self) -> impl Iterator<Item = C> + '_ {
+        self.terrain_elev_m
+            .iter()
+            .zip(self.earth_bulge_m.iter())
+            .map(|(&elev_m, &bulge_m)| elev_m + bulge_m)
+    }
+
+    /// Returns an iterator over LOS bent down by earth bulge.
+    ///
+    /// Pair this with the raw `terrain_elev_m`, which keeps terrain
+    /// at its true elevation.
+    pub fn curved_los_iter(&self) -> impl Iterator<Item = C> + '_ {
+        self.los_elev_m
+            .iter()
+            .zip(self.earth_bulge_m.iter())
+            .map(|(&los_m, &bulge_m)| los_m - bulge_m)
+    }
+}
+
+/// Altitude of a path endpoint.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Altitude<C> {
+    /// Meters above ground level.
+    Agl(C),
+
+    /// Meters above mean sea level (the geoid).
+    Amsl(C),
+
+    /// Meters above the WGS84 ellipsoid, as reported by GPS.
+    ///
+    /// Unless tiles are [ellipsoidal](Tiles::ellipsoidal), they need
+    /// a [geoid](Tiles::with_geoid) to resolve this.
+    Ellipsoidal(C),
+}
+
+/// Height of a path endpoint, both above ground and above mean sea
+/// level.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalHeight<C> {
+    /// Meters above ground level.
+    pub agl_m: C,
+
+    /// Meters above mean sea level, or above the ellipsoid if built
+    /// from [ellipsoidal](Tiles::ellipsoidal) tiles.
+    pub amsl_m: C,
+}
+
+/// Land cover sampled at each step of a [`Profile`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileClutter<C> {
+    /// Clutter class code at each step, [`NO_CLASS`] where no raster
+    /// provides coverage.
+    ///
+    /// [`NO_CLASS`]: crate::NO_CLASS
+    pub classes: Box<[u8]>,
+
+    /// Terrain elevation plus clutter height at each step.
+    pub surface_elev_m: Box<[C]>,
+}
+
+/// Structures burned into each step of a [`Profile`].
+///
+/// A structure the path crosses between two steps, without any step
+/// landing inside it, is assigned to the nearer of the two steps.
+/// Where structures overlap, the tallest wins.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileFootprints<C> {
+    /// [`Footprint::id`](crate::Footprint::id) of the structure at
+    /// each step.
+    pub ids: Box<[Option<u64>]>,
+
+    /// Height of the structure above ground at each step, 0 where
+    /// there is none.
+    pub heights_m: Box<[C]>,
+
+    /// Terrain elevation plus structure height at each step.
+    pub surface_elev_m: Box<[C]>,
 }
 
 impl<C> Profile<C>
@@ -37,15 +157,17 @@ where
             start: None,
             max_step_m: None,
             end: None,
-            start_alt_m: C::zero(),
-            end_alt_m: C::zero(),
+            start_alt: Altitude::Agl(C::zero()),
+            end_alt: Altitude::Agl(C::zero()),
             earth_curve: false,
-            normalize: false,
             earth_radius: C::from(MEAN_EARTH_RADIUS).unwrap(),
+            clutter: None,
+            footprints: None,
         }
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct ProfileBuilder<C: CoordFloat = f32> {
     /// Start point of the path (required).
     start: Option<Coord<C>>,
@@ -56,22 +178,23 @@ pub struct ProfileBuilder<C: CoordFloat = f32> {
     /// End point of the path (required).
     end: Option<Coord<C>>,
 
-    /// Starting altitude above ground (meters, defaults to 0).
-    start_alt_m: C,
+    /// Starting altitude (defaults to 0 m AGL).
+    start_alt: Altitude<C>,
 
-    /// Starting altitude above ground (meters, defaults to 0).
-    end_alt_m: C,
+    /// Ending altitude (defaults to 0 m AGL).
+    end_alt: Altitude<C>,
 
-    /// Add earth curvature (defaults to false).
+    /// Compute earth bulge (defaults to false).
     earth_curve: bool,
 
-    /// Place virtual earth curve as the highest and center point of
-    /// the output (defaults to false; has no effect if `earth_curve`
-    /// is `false`).
-    normalize: bool,
-
     /// Earth radius, defaults to [MEAN_EARTH_RADIUS].
     earth_radius: C,
+
+    /// Land cover layer sampled alongside terrain (optional).
+    clutter: Option<Arc<Clutter>>,
+
+    /// Structures burned into the profile (optional).
+    footprints: Option<Arc<Footprints>>,
 }
 
 impl<C> ProfileBuilder<C>
@@ -89,7 +212,32 @@ where
     /// Starting altitude above ground (meters, defaults to 0).
     #[must_use]
     pub fn start_alt(mut self, meters: C) -> Self {
-        self.start_alt_m = meters;
+        self.start_alt = Altitude::Agl(meters);
+        self
+    }
+
+    /// Starting altitude above mean sea level (meters).
+    ///
+    /// Building fails if this is below ground.
+    #[must_use]
+    pub fn start_alt_amsl(mut self, meters: C) -> Self {
+        self.start_alt = Altitude::Amsl(meters);
+        self
+    }
+
+    /// Starting altitude above the WGS84 ellipsoid (meters).
+    ///
+    /// Building fails if this is below ground.
+    #[must_use]
+    pub fn start_alt_ellipsoidal(mut self, meters: C) -> Self {
+        self.start_alt = Altitude::Ellipsoidal(meters);
+        self
+    }
+
+    /// Starting altitude (defaults to 0 m AGL).
+    #[must_use]
+    pub fn start_altitude(mut self, altitude: Altitude<C>) -> Self {
+        self.start_alt = altitude;
         self
     }
 
@@ -107,36 +255,79 @@ where
         self
     }
 
-    /// Starting altitude above ground (meters, defaults to 0).
+    /// Ending altitude above ground (meters, defaults to 0).
     #[must_use]
     pub fn end_alt(mut self, meters: C) -> Self {
-        self.end_alt_m = meters;
+        self.end_alt = Altitude::Agl(meters);
         self
     }
 
-    /// Add earth curvature (defaults to false).
+    /// Ending altitude above mean sea level (meters).
+    ///
+    /// Building fails if this is below ground.
     #[must_use]
-    pub fn earth_curve(mut self, add_curve: bool) -> Self {
-        self.earth_curve = add_curve;
+    pub fn end_alt_amsl(mut self, meters: C) -> Self {
+        self.end_alt = Altitude::Amsl(meters);
         self
     }
 
-    /// Place virtual earth curve as the highest and center point of
-    /// the output (defaults to false; has no effect if `earth_curve`
-    /// is `false`).
+    /// Ending altitude above the WGS84 ellipsoid (meters).
+    ///
+    /// Building fails if this is below ground.
     #[must_use]
-    pub fn normalize(mut self, normalize: bool) -> Self {
-        self.normalize = normalize;
+    pub fn end_alt_ellipsoidal(mut self, meters: C) -> Self {
+        self.end_alt = Altitude::Ellipsoidal(meters);
+        self
+    }
+
+    /// Ending altitude (defaults to 0 m AGL).
+    #[must_use]
+    pub fn end_altitude(mut self, altitude: Altitude<C>) -> Self {
+        self.end_alt = altitude;
+        self
+    }
+
+    /// Compute earth bulge (defaults to false).
+    ///
+    /// Terrain elevations are left untouched; see
+    /// [`Profile::earth_bulge_m`].
+    #[must_use]
+    pub fn earth_curve(mut self, add_curve: bool) -> Self {
+        self.earth_curve = add_curve;
         self
     }
 
     /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    ///
+    /// Use an effective earth radius (e.g., 4/3 of mean) to account
+    /// for atmospheric refraction.
     #[must_use]
     pub fn earth_radius(mut self, earth_radius_m: C) -> Self {
         self.earth_radius = earth_radius_m;
         self
     }
 
+    /// Land cover layer to sample alongside terrain (optional).
+    ///
+    /// Clutter does not affect `terrain_elev_m` or `los_elev_m`; see
+    /// [`Profile::clutter`].
+    #[must_use]
+    pub fn clutter(mut self, clutter: Arc<Clutter>) -> Self {
+        self.clutter = Some(clutter);
+        self
+    }
+
+    /// Building and structure footprints to burn into the profile
+    /// (optional).
+    ///
+    /// Footprints do not affect `terrain_elev_m` or `los_elev_m`; see
+    /// [`Profile::footprints`].
+    #[must_use]
+    pub fn footprints(mut self, footprints: Arc<Footprints>) -> Self {
+        self.footprints = Some(footprints);
+        self
+    }
+
     pub fn build(&self, tiles: &Tiles) -> Result<Profile<C>, TerrainError>
     where
         C: FloatConst + AsPrimitive<usize>,
@@ -157,7 +348,7 @@ where
             (great_circle, runtime)
         };
 
-        let (mut terrain_elev_m, terrain_runtime) = {
+        let (terrain_elev_m, terrain_runtime) = {
             let mut terrain = Vec::with_capacity(great_circle.len());
             let now = std::time::Instant::now();
             let mut tile = tiles.get(Coord {
@@ -170,64 +361,71 @@ where
                     x: point.0.x.into(),
                     y: point.0.y.into(),
                 };
-                if let Some(elevation) = tile.get(coord) {
-                    terrain.push(C::from(elevation).unwrap());
-                } else {
-                    tile = tiles.get(coord)?;
-                    let elevation = tile.get_unchecked(coord);
-                    terrain.push(C::from(elevation).unwrap());
-                }
+                terrain.push(C::from(tiles.sample_m(&mut tile, coord)?).unwrap());
             }
 
             let runtime = now.elapsed();
             (terrain.into_boxed_slice(), runtime)
         };
 
-        let distances_m: Box<[C]> = linspace(C::zero(), distance_m, terrain_elev_m.len()).collect();
+        let clutter = self.clutter.as_deref().map(|clutter| {
+            let classes: Box<[u8]> = great_circle
+                .iter()
+                .map(|point| {
+                    clutter.class(Coord {
+                        x: point.0.x.into(),
+                        y: point.0.y.into(),
+                    })
+                })
+                .collect();
+            let surface_elev_m = classes
+                .iter()
+                .zip(terrain_elev_m.iter())
+                .map(|(&class, &elev_m)| elev_m + C::from(clutter.table().height_m(class)).unwrap())
+                .collect();
+            ProfileClutter {
+                classes,
+                surface_elev_m,
+            }
+        });
 
-        let _earth_curve_runtime = {
-            let now = std::time::Instant::now();
-            if self.earth_curve {
-                // https://www.trailnotes.org/SizeOfTheEarth/
-                let earth_radius = self.earth_radius;
-                let start_elev_alt =
-                    *terrain_elev_m.first().unwrap() + C::from(self.start_alt_m).unwrap();
-                let start_radius_m = earth_radius + start_elev_alt;
-                let end_elev_alt =
-                    *terrain_elev_m.last().unwrap() + C::from(self.end_alt_m).unwrap();
-                let elev_angle_rad =
-                    elevation_angle(start_elev_alt, distance_m, end_elev_alt, self.earth_radius);
-
-                let (nb, nm) = if self.normalize {
-                    let nb = -start_elev_alt;
-                    let nm = (-end_elev_alt - nb) / distance_m;
-                    (nb, nm)
-                } else {
-                    (C::zero(), C::zero())
-                };
+        let footprints = self
+            .footprints
+            .as_deref()
+            .map(|footprints| Self::burn_footprints(footprints, &great_circle, &terrain_elev_m));
 
-                for (&d_distance_m, elev_m) in distances_m.iter().zip(terrain_elev_m.iter_mut()) {
-                    let radius_m = C::from(*elev_m).unwrap() + earth_radius;
-                    // Approximate angle when radius is much larger than distance.
-                    let chord_angle_rad = d_distance_m / radius_m;
-                    let c_unk_unit = start_radius_m * (elev_angle_rad + C::FRAC_PI_2()).sin()
-                        / (C::FRAC_PI_2() - elev_angle_rad - chord_angle_rad).sin();
-                    let height_m = if self.normalize {
-                        let los_m = -(nm * d_distance_m) - nb;
-                        (radius_m - c_unk_unit) + los_m
-                    } else {
-                        radius_m - c_unk_unit
-                    };
-                    *elev_m = height_m;
-                }
-            }
+        let start_height = Self::terminal_height(
+            "start",
+            tiles,
+            start,
+            self.start_alt,
+            *terrain_elev_m.first().unwrap(),
+        )?;
+        let end_height = Self::terminal_height(
+            "end",
+            tiles,
+            end,
+            self.end_alt,
+            *terrain_elev_m.last().unwrap(),
+        )?;
 
-            now.elapsed()
+        let distances_m: Box<[C]> = linspace(C::zero(), distance_m, terrain_elev_m.len()).collect();
+
+        let earth_bulge_m: Box<[C]> = if self.earth_curve {
+            let two = C::one() + C::one();
+            distances_m
+                .iter()
+                .map(|&d_distance_m| {
+                    d_distance_m * (distance_m - d_distance_m) / (two * self.earth_radius)
+                })
+                .collect()
+        } else {
+            vec![C::zero(); distances_m.len()].into_boxed_slice()
         };
 
         let los_elev_m: Box<[C]> = linspace(
-            *terrain_elev_m.first().unwrap() + C::from(self.start_alt_m).unwrap(),
-            *terrain_elev_m.last().unwrap() + C::from(self.end_alt_m).unwrap(),
+            *terrain_elev_m.first().unwrap() + start_height.agl_m,
+            *terrain_elev_m.last().unwrap() + end_height.agl_m,
             terrain_elev_m.len(),
         )
         .collect();
@@ -242,23 +440,557 @@ where
         assert!(
             distances_m.len() == great_circle.len()
                 && great_circle.len() == terrain_elev_m.len()
-                && terrain_elev_m.len() == los_elev_m.len()
+                && terrain_elev_m.len() == earth_bulge_m.len()
+                && earth_bulge_m.len() == los_elev_m.len()
         );
 
         Ok(Profile {
             distances_m,
             great_circle,
             terrain_elev_m,
+            earth_bulge_m,
             los_elev_m,
+            start_height,
+            end_height,
+            clutter,
+            footprints,
         })
     }
 }
 
+/// Private API.
+impl<C> ProfileBuilder<C>
+where
+    C: CoordFloat,
+    f64: From<C>,
+{
+    /// Samples `footprints` at, and between, each point of
+    /// `great_circle`.
+    fn burn_footprints(
+        footprints: &Footprints,
+        great_circle: &[Point<C>],
+        terrain_elev_m: &[C],
+    ) -> ProfileFootprints<C> {
+        let coords: Vec<Coord<f64>> = great_circle
+            .iter()
+            .map(|point| Coord {
+                x: point.0.x.into(),
+                y: point.0.y.into(),
+            })
+            .collect();
+        let mut burned: Vec<Option<(u64, f32)>> = coords
+            .iter()
+            .map(|&coord| {
+                footprints
+                    .at(coord)
+                    .map(|footprint| (footprint.id, footprint.height_m))
+            })
+            .collect();
+
+        // Catch structures narrower than a step.
+        for (idx, pair) in coords.windows(2).enumerate() {
+            let (start, end) = (pair[0], pair[1]);
+            for footprint in footprints.intersecting(Line::new(start, end)) {
+                let id = Some(footprint.id);
+                if burned[idx].map(|(id, _)| id) == id || burned[idx + 1].map(|(id, _)| id) == id {
+                    continue;
+                }
+                let Some(rect) = footprint.polygon.bounding_rect() else {
+                    continue;
+                };
+                let center = rect.center();
+                let dist_sq = |coord: Coord<f64>| {
+                    let delta = coord - center;
+                    delta.x * delta.x + delta.y * delta.y
+                };
+                let nearest = if dist_sq(start) <= dist_sq(end) {
+                    idx
+                } else {
+                    idx + 1
+                };
+                if !matches!(burned[nearest], Some((_, height_m)) if height_m >= footprint.height_m)
+                {
+                    burned[nearest] = Some((footprint.id, footprint.height_m));
+                }
+            }
+        }
+
+        let ids = burned.iter().map(|burn| burn.map(|(id, _)| id)).collect();
+        let heights_m: Box<[C]> = burned
+            .iter()
+            .map(|burn| burn.map_or(C::zero(), |(_, height_m)| C::from(height_m).unwrap()))
+            .collect();
+        let surface_elev_m = terrain_elev_m
+            .iter()
+            .zip(heights_m.iter())
+            .map(|(&elev_m, &height_m)| elev_m + height_m)
+            .collect();
+        ProfileFootprints {
+            ids,
+            heights_m,
+            surface_elev_m,
+        }
+    }
+
+    /// Resolves `altitude` at `coord` over `ground_m` into both AGL
+    /// and `tiles`' vertical datum.
+    fn terminal_height(
+        end: &'static str,
+        tiles: &Tiles,
+        coord: Coord<C>,
+        altitude: Altitude<C>,
+        ground_m: C,
+    ) -> Result<TerminalHeight<C>, TerrainError> {
+        let convert = |meters: C, to_datum: fn(&Geoid, Coord<f64>, f64) -> Result<f64, _>| {
+            let geoid = tiles.geoid().ok_or(TerrainError::NoGeoid)?;
+            let coord = Coord {
+                x: coord.x.into(),
+                y: coord.y.into(),
+            };
+            to_datum(geoid, coord, meters.into()).map(|meters| C::from(meters).unwrap())
+        };
+        let amsl_m = match altitude {
+            Altitude::Agl(agl_m) => {
+                return Ok(TerminalHeight {
+                    agl_m,
+                    amsl_m: ground_m + agl_m,
+                })
+            }
+            Altitude::Amsl(amsl_m) if tiles.is_ellipsoidal() => {
+                convert(amsl_m, Geoid::to_ellipsoidal)?
+            }
+            Altitude::Ellipsoidal(ellipsoidal_m) if !tiles.is_ellipsoidal() => {
+                convert(ellipsoidal_m, Geoid::to_orthometric)?
+            }
+            Altitude::Amsl(meters) | Altitude::Ellipsoidal(meters) => meters,
+        };
+        if amsl_m < ground_m {
+            return Err(TerrainError::BelowGround {
+                end,
+                amsl_m: amsl_m.into(),
+                ground_m: ground_m.into(),
+            });
+        }
+        Ok(TerminalHeight {
+            agl_m: amsl_m - ground_m,
+            amsl_m,
+        })
+    }
+}
+
+/// Parallel batch generation.
+///
+/// These methods use `self` as a template: every setting except the
+/// endpoints is shared by all generated profiles. Work is spread
+/// over rayon's global thread pool, and `tiles` is shared between
+/// workers so each tile is loaded at most once.
+impl<C> ProfileBuilder<C>
+where
+    C: CoordFloat + FromPrimitive + FloatConst + AsPrimitive<usize> + Send + Sync,
+    f64: From<C>,
+{
+    /// Returns a parallel iterator over profiles for each
+    /// `(start, end)` pair.
+    ///
+    /// Items are yielded in the same order as `pairs` when collected,
+    /// but can also be consumed as they are completed with
+    /// [`ParallelIterator::for_each`] and friends. A failing pair
+    /// results in an `Err` item and does not affect the others.
+    pub fn par_build<'a>(
+        &'a self,
+        tiles: &'a Tiles,
+        pairs: &'a [(Coord<C>, Coord<C>)],
+    ) -> impl IndexedParallelIterator<Item = Result<Profile<C>, TerrainError>> + 'a {
+        pairs
+            .par_iter()
+            .map(move |&(start, end)| self.clone().start(start).end(end).build(tiles))
+    }
+
+    /// Builds a profile for each `(start, end)` pair.
+    ///
+    /// Returned results are in the same order as `pairs`.
+    pub fn build_pairs(
+        &self,
+        tiles: &Tiles,
+        pairs: &[(Coord<C>, Coord<C>)],
+    ) -> Vec<Result<Profile<C>, TerrainError>> {
+        self.par_build(tiles, pairs).collect()
+    }
+
+    /// Builds a profile from this builder's `start` to each of
+    /// `ends`.
+    ///
+    /// Returned results are in the same order as `ends`.
+    pub fn build_one_to_many(
+        &self,
+        tiles: &Tiles,
+        ends: &[Coord<C>],
+    ) -> Result<Vec<Result<Profile<C>, TerrainError>>, TerrainError> {
+        let start = self.start.ok_or(TerrainError::Builder("start"))?;
+        let pairs: Vec<_> = ends.iter().map(|&end| (start, end)).collect();
+        Ok(self.build_pairs(tiles, &pairs))
+    }
+
+    /// Builds a profile between every unique pair of `points`.
+    ///
+    /// Each result is tagged with the indices `(i, j)`, where `i <
+    /// j`, of its start and end in `points`. Results are ordered by
+    /// `i`, then `j`.
+    #[allow(clippy::type_complexity)]
+    pub fn build_many_to_many(
+        &self,
+        tiles: &Tiles,
+        points: &[Coord<C>],
+    ) -> Vec<((usize, usize), Result<Profile<C>, TerrainError>)> {
+        let indices: Vec<(usize, usize)> = (0..points.len())
+            .flat_map(|i| ((i + 1)..points.len()).map(move |j| (i, j)))
+            .collect();
+        let pairs: Vec<_> = indices
+            .iter()
+            .map(|&(i, j)| (points[i], points[j]))
+            .collect();
+        indices
+            .into_iter()
+            .zip(self.build_pairs(tiles, &pairs))
+            .collect()
+    }
+}
+
+/// Obstruction analysis.
+impl<C> Profile<C>
+where
+    C: CoordFloat,
+{
+    /// Returns the obstacles along this profile's line of sight.
+    ///
+    /// Terrain is compared against LOS as seen by
+    /// [`flat_earth_terrain_iter`](Self::flat_earth_terrain_iter), so
+    /// earth bulge follows the builder's `earth_curve` and
+    /// `earth_radius` settings.
+    pub fn obstructions(&self) -> Obstructions<C> {
+        let terrain_elev_m: Vec<C> = self.flat_earth_terrain_iter().collect();
+        Obstructions::new(&self.distances_m, &terrain_elev_m, &self.los_elev_m)
+    }
+}
+
+/// A single terrain sample of interest along a profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obstacle<C: CoordFloat = f32> {
+    /// Index of the sample in the profile.
+    pub index: usize,
+
+    /// Distance from the start of the profile.
+    pub distance_m: C,
+
+    /// Terrain elevation, including any earth bulge.
+    pub elev_m: C,
+
+    /// Height of the terrain above LOS, negative when the terrain is
+    /// below LOS.
+    pub excess_m: C,
+}
+
+/// The horizon as seen from one end of a profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Horizon<C: CoordFloat = f32> {
+    /// Index of the horizon sample in the profile.
+    pub index: usize,
+
+    /// Distance from the observing end to the horizon.
+    pub distance_m: C,
+
+    /// Elevation angle from the observing end to the horizon
+    /// (radians, small angle approximation).
+    ///
+    /// Angles are measured over terrain raised by earth bulge, which
+    /// reads half the path's central angle higher than on a curved
+    /// earth.
+    pub elevation_angle_rad: C,
+}
+
+/// Obstacles along a profile's line of sight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Obstructions<C: CoordFloat = f32> {
+    /// The interior sample closest to, or furthest above, LOS. `None`
+    /// if the profile has no interior samples.
+    pub min_clearance: Option<Obstacle<C>>,
+
+    /// Horizons as seen from the start and end of the profile,
+    /// respectively.
+    ///
+    /// Like ITM's `FindHorizons`, if no terrain rises above the line
+    /// to the other end, that end is the horizon.
+    pub horizons: [Horizon<C>; 2],
+
+    /// The highest sample of each run of terrain penetrating LOS,
+    /// ordered by distance.
+    pub peaks: Box<[Obstacle<C>]>,
+
+    /// Area between LOS and the terrain above it (m²).
+    pub intersection_area_m2: C,
+}
+
+impl<C> Obstructions<C>
+where
+    C: CoordFloat,
+{
+    /// Analyzes raw profile vectors.
+    ///
+    /// `terrain_elev_m` should already include any earth bulge, as
+    /// from [`Profile::flat_earth_terrain_iter`]. See
+    /// [`Profile::obstructions`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inputs have different lengths or fewer than two
+    /// samples.
+    pub fn new(distances_m: &[C], terrain_elev_m: &[C], los_elev_m: &[C]) -> Self {
+        assert!(
+            distances_m.len() == terrain_elev_m.len() && terrain_elev_m.len() == los_elev_m.len()
+        );
+        assert!(distances_m.len() >= 2);
+
+        let two = C::one() + C::one();
+        let last = distances_m.len() - 1;
+        let total_distance_m = distances_m[last];
+        let (start_elev_m, end_elev_m) = (los_elev_m[0], los_elev_m[last]);
+
+        let obstacle = |index: usize| {
+            let elev_m = terrain_elev_m[index];
+            Obstacle {
+                index,
+                distance_m: distances_m[index],
+                elev_m,
+                excess_m: elev_m - los_elev_m[index],
+            }
+        };
+
+        let slope = (end_elev_m - start_elev_m) / total_distance_m;
+        let mut horizons = [
+            Horizon {
+                index: last,
+                distance_m: total_distance_m,
+                elevation_angle_rad: slope,
+            },
+            Horizon {
+                index: 0,
+                distance_m: total_distance_m,
+                elevation_angle_rad: -slope,
+            },
+        ];
+
+        let mut min_clearance: Option<Obstacle<C>> = None;
+        let mut peaks = Vec::new();
+        let mut peak: Option<Obstacle<C>> = None;
+
+        for index in 1..last {
+            let from_start_m = distances_m[index];
+            let from_end_m = total_distance_m - from_start_m;
+            let elev_m = terrain_elev_m[index];
+            for (horizon, (site_elev_m, distance_m)) in horizons
+                .iter_mut()
+                .zip([(start_elev_m, from_start_m), (end_elev_m, from_end_m)])
+            {
+                let angle = (elev_m - site_elev_m) / distance_m;
+                if angle > horizon.elevation_angle_rad {
+                    *horizon = Horizon {
+                        index,
+                        distance_m,
+                        elevation_angle_rad: angle,
+                    };
+                }
+            }
+
+            let candidate = obstacle(index);
+            let min = min_clearance.get_or_insert(candidate);
+            if candidate.excess_m > min.excess_m {
+                *min = candidate;
+            }
+            if candidate.excess_m > C::zero() {
+                let peak = peak.get_or_insert(candidate);
+                if candidate.excess_m > peak.excess_m {
+                    *peak = candidate;
+                }
+            } else {
+                peaks.extend(peak.take());
+            }
+        }
+        peaks.extend(peak);
+
+        let intersection_area_m2 = (1..=last)
+            .map(|index| {
+                let (a, b) = (obstacle(index - 1), obstacle(index));
+                let dx = b.distance_m - a.distance_m;
+                dx * (a.excess_m.max(C::zero()) + b.excess_m.max(C::zero())) / two
+            })
+            .fold(C::zero(), |acc, area| acc + area);
+
+        Self {
+            min_clearance,
+            horizons,
+            peaks: peaks.into_boxed_slice(),
+            intersection_area_m2,
+        }
+    }
+
+    /// Returns `true` if no terrain penetrates LOS.
+    pub fn is_clear(&self) -> bool {
+        self.peaks.is_empty()
+    }
+}
+
+/// Terrain statistics.
+impl<C> Profile<C>
+where
+    C: CoordFloat,
+{
+    /// Returns roughness statistics of this profile's terrain.
+    pub fn stats(&self) -> ProfileStats<C> {
+        ProfileStats::new(&self.distances_m, &self.terrain_elev_m)
+    }
+}
+
+/// Terrain roughness statistics of a profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfileStats<C: CoordFloat = f32> {
+    /// Terrain irregularity parameter Δh: the interdecile range of
+    /// terrain heights about a least squares fit line, over the
+    /// central 80% of the path.
+    ///
+    /// Note that ITM applies its own distance dependent correction to
+    /// Δh, which is not included here.
+    pub delta_h_m: C,
+
+    /// RMS of terrain heights about a least squares fit line, over
+    /// the whole path.
+    pub rms_roughness_m: C,
+
+    /// Mean absolute slope between neighboring samples (m/m).
+    pub mean_slope: C,
+
+    /// Percentage of the path's length where neighboring samples
+    /// have identical elevations, which is how NASADEM represents
+    /// water bodies.
+    pub flat_percent: C,
+}
+
+impl<C> ProfileStats<C>
+where
+    C: CoordFloat,
+{
+    /// Computes statistics from raw profile vectors.
+    ///
+    /// See [`Profile::stats`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inputs have different lengths or are empty.
+    pub fn new(distances_m: &[C], terrain_elev_m: &[C]) -> Self {
+        assert_eq!(distances_m.len(), terrain_elev_m.len());
+        assert!(!distances_m.is_empty());
+
+        let total_distance_m = distances_m[distances_m.len() - 1];
+        let decile = C::from(0.1).unwrap();
+
+        let (central_distances_m, central_elev_m): (Vec<C>, Vec<C>) = distances_m
+            .iter()
+            .zip(terrain_elev_m)
+            .filter(|(&distance_m, _)| {
+                distance_m >= total_distance_m * decile
+                    && distance_m <= total_distance_m * (C::one() - decile)
+            })
+            .unzip();
+        let delta_h_m = if central_elev_m.len() < 2 {
+            C::zero()
+        } else {
+            let mut residuals = detrend(&central_distances_m, &central_elev_m);
+            // Widening to f64 is exact, and gives NaN a total order.
+            residuals.sort_by(|a, b| {
+                let (a, b) = (a.to_f64().unwrap(), b.to_f64().unwrap());
+                a.total_cmp(&b)
+            });
+            quantile(&residuals, C::one() - decile) - quantile(&residuals, decile)
+        };
+
+        let residuals = detrend(distances_m, terrain_elev_m);
+        let rms_roughness_m = (residuals
+            .iter()
+            .fold(C::zero(), |acc, residual| acc + residual.powi(2))
+            / C::from(residuals.len()).unwrap())
+        .sqrt();
+
+        let (slope_sum, segments, flat_m) =
+            distances_m.windows(2).zip(terrain_elev_m.windows(2)).fold(
+                (C::zero(), C::zero(), C::zero()),
+                |(slope_sum, segments, flat_m), (d, h)| {
+                    let dx = d[1] - d[0];
+                    let dy = h[1] - h[0];
+                    let slope = if dx > C::zero() {
+                        (dy / dx).abs()
+                    } else {
+                        C::zero()
+                    };
+                    let flat_m = if dy == C::zero() { flat_m + dx } else { flat_m };
+                    (slope_sum + slope, segments + C::one(), flat_m)
+                },
+            );
+        let mean_slope = if segments > C::zero() {
+            slope_sum / segments
+        } else {
+            C::zero()
+        };
+        let flat_percent = if total_distance_m > C::zero() {
+            C::from(100).unwrap() * flat_m / total_distance_m
+        } else {
+            C::zero()
+        };
+
+        Self {
+            delta_h_m,
+            rms_roughness_m,
+            mean_slope,
+            flat_percent,
+        }
+    }
+}
+
+/// Returns the residuals of `ys` about their least squares fit line.
+fn detrend<C: CoordFloat>(xs: &[C], ys: &[C]) -> Vec<C> {
+    let n = C::from(xs.len()).unwrap();
+    let x_mean = xs.iter().fold(C::zero(), |acc, &x| acc + x) / n;
+    let y_mean = ys.iter().fold(C::zero(), |acc, &y| acc + y) / n;
+    let (covariance, variance) =
+        xs.iter()
+            .zip(ys)
+            .fold((C::zero(), C::zero()), |(cov, var), (&x, &y)| {
+                let dx = x - x_mean;
+                (cov + dx * (y - y_mean), var + dx * dx)
+            });
+    let slope = if variance > C::zero() {
+        covariance / variance
+    } else {
+        C::zero()
+    };
+    xs.iter()
+        .zip(ys)
+        .map(|(&x, &y)| y - (y_mean + slope * (x - x_mean)))
+        .collect()
+}
+
+/// Returns the `q` quantile of `sorted`, linearly interpolating
+/// between the closest ranks.
+fn quantile<C: CoordFloat>(sorted: &[C], q: C) -> C {
+    let rank = q * C::from(sorted.len() - 1).unwrap();
+    let lo = rank.floor().to_usize().unwrap();
+    let hi = rank.ceil().to_usize().unwrap();
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - rank.floor())
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::excessive_precision)]
 
-    use super::{Coord, Profile, Tiles};
+    use super::{Coord, Obstructions, Profile, ProfileStats, TerminalHeight, Tiles};
     use crate::tiles::TileMode;
 
     /// ```xml
@@ -329,4 +1061,280 @@ mod tests {
             .unwrap();
         assert_eq!(36, profile.great_circle.len());
     }
+
+    #[test]
+    fn test_batch_matches_sequential() {
+        let points = [
+            Coord {
+                x: -71.308_307_164_413_69,
+                y: 44.283_098_066_031_65,
+            },
+            Coord {
+                x: -71.297_207_328_376_8,
+                y: 44.256_280_984_242_78,
+            },
+            Coord {
+                x: -71.303_25,
+                y: 44.270_5,
+            },
+            Coord { x: -71.4, y: 44.3 },
+        ];
+
+        let tile_source = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let builder = Profile::builder().start(points[0]).max_step(90.0);
+
+        let one_to_many = builder
+            .build_one_to_many(&tile_source, &points[1..])
+            .unwrap();
+        assert_eq!(one_to_many.len(), 3);
+        for (end, batched) in points[1..].iter().zip(one_to_many) {
+            let sequential = builder.clone().end(*end).build(&tile_source).unwrap();
+            assert_eq!(batched.unwrap(), sequential);
+        }
+
+        let many_to_many = builder.build_many_to_many(&tile_source, &points);
+        let indices: Vec<_> = many_to_many.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(
+            indices,
+            [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)].to_vec()
+        );
+        for ((i, j), batched) in many_to_many {
+            let sequential = builder
+                .clone()
+                .start(points[i])
+                .end(points[j])
+                .build(&tile_source)
+                .unwrap();
+            assert_eq!(batched.unwrap(), sequential);
+        }
+    }
+
+    #[test]
+    fn test_batch_reports_per_pair_errors() {
+        let tile_source = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let builder = Profile::<f64>::builder();
+        let pairs = [(Coord { x: -71.3, y: 44.2 }, Coord { x: -71.2, y: 44.3 })];
+        let results = builder.build_pairs(&tile_source, &pairs);
+        assert!(matches!(
+            results.as_slice(),
+            [Err(crate::TerrainError::Builder("max_step"))]
+        ));
+        assert!(matches!(
+            builder.build_one_to_many(&tile_source, &[pairs[0].1]),
+            Err(crate::TerrainError::Builder("start"))
+        ));
+    }
+
+    #[test]
+    fn test_obstructions() {
+        //       peak   peak
+        //        /\    /\
+        // ------/--\--/--\------ LOS at 10 m
+        //    __/    \/    \__
+        let distances_m = [0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0];
+        let terrain_elev_m = [0.0, 5.0, 15.0, 5.0, 12.0, 14.0, 5.0, 0.0];
+        let los_elev_m = [10.0; 8];
+        let obstructions = Obstructions::new(&distances_m, &terrain_elev_m, &los_elev_m);
+
+        assert!(!obstructions.is_clear());
+        let peaks: Vec<_> = obstructions
+            .peaks
+            .iter()
+            .map(|peak| (peak.index, peak.excess_m))
+            .collect();
+        assert_eq!(peaks, [(2, 5.0), (5, 4.0)]);
+        let min_clearance = obstructions.min_clearance.unwrap();
+        assert_eq!(min_clearance.index, 2);
+        assert_eq!(min_clearance.elev_m, 15.0);
+        assert_eq!(min_clearance.distance_m, 20.0);
+
+        // Each end sees the nearest peak as its horizon.
+        let [start_horizon, end_horizon] = obstructions.horizons;
+        assert_eq!(start_horizon.index, 2);
+        assert_eq!(start_horizon.elevation_angle_rad, 0.25);
+        assert_eq!(end_horizon.index, 5);
+        assert_eq!(end_horizon.distance_m, 20.0);
+        assert_eq!(end_horizon.elevation_angle_rad, 0.2);
+
+        // Trapezoids over the positive excess heights [0, 0, 5, 0, 2,
+        // 4, 0, 0] at 10 m spacing.
+        assert_eq!(obstructions.intersection_area_m2, 110.0);
+    }
+
+    #[test]
+    fn test_obstructions_clear_los() {
+        let distances_m = [0.0, 1_000.0, 2_000.0];
+        let terrain_elev_m = [0.0, 0.0, 0.0];
+        let los_elev_m = [10.0, 10.0, 10.0];
+
+        let flat = Obstructions::new(&distances_m, &terrain_elev_m, &los_elev_m);
+        assert!(flat.is_clear());
+        assert_eq!(flat.intersection_area_m2, 0.0);
+        assert_eq!(flat.min_clearance.unwrap().excess_m, -10.0);
+        // Nothing rises above the line between the ends, so each end
+        // is the other's horizon.
+        assert_eq!(flat.horizons[0].index, 2);
+        assert_eq!(flat.horizons[1].index, 0);
+        assert_eq!(flat.horizons[0].elevation_angle_rad, 0.0);
+
+        // The 20 m mid-path bulge of a tiny earth blocks LOS.
+        let profile = Profile {
+            distances_m: distances_m.into(),
+            great_circle: vec![geo::Point::new(0.0, 0.0); 3].into(),
+            terrain_elev_m: terrain_elev_m.into(),
+            earth_bulge_m: [0.0, 20.0, 0.0].into(),
+            los_elev_m: los_elev_m.into(),
+            start_height: TerminalHeight {
+                agl_m: 10.0,
+                amsl_m: 10.0,
+            },
+            end_height: TerminalHeight {
+                agl_m: 10.0,
+                amsl_m: 10.0,
+            },
+            clutter: None,
+            footprints: None,
+        };
+        let curved = profile.obstructions();
+        assert!(!curved.is_clear());
+        assert_eq!(curved.peaks[0].elev_m, 20.0);
+        assert_eq!(curved.peaks[0].excess_m, 10.0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let distances_m: Vec<f64> = (0..=10).map(|idx| f64::from(idx) * 10.0).collect();
+
+        // A ramp has no roughness.
+        let ramp: Vec<f64> = distances_m.iter().map(|d| d * 0.5).collect();
+        let stats = ProfileStats::new(&distances_m, &ramp);
+        approx::assert_abs_diff_eq!(stats.delta_h_m, 0.0, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(stats.rms_roughness_m, 0.0, epsilon = 1e-9);
+        approx::assert_relative_eq!(stats.mean_slope, 0.5);
+        assert_eq!(stats.flat_percent, 0.0);
+
+        // A 10 m sawtooth, whose central 80% is symmetric about its
+        // mean.
+        let sawtooth: Vec<f64> = (0..=10).map(|idx| f64::from(idx % 2) * 10.0).collect();
+        let stats = ProfileStats::new(&distances_m, &sawtooth);
+        approx::assert_relative_eq!(stats.delta_h_m, 10.0, epsilon = 1e-9);
+        assert!(stats.rms_roughness_m > 4.0 && stats.rms_roughness_m < 5.5);
+        approx::assert_relative_eq!(stats.mean_slope, 1.0);
+        assert_eq!(stats.flat_percent, 0.0);
+
+        let plateau = [0.0, 0.0, 10.0, 10.0, 10.0];
+        let stats = ProfileStats::new(&distances_m[..5], &plateau);
+        approx::assert_relative_eq!(stats.mean_slope, 0.25);
+        approx::assert_relative_eq!(stats.flat_percent, 75.0);
+
+        // Voids propagate instead of panicking.
+        let mut void = sawtooth.clone();
+        void[5] = f64::NAN;
+        assert!(ProfileStats::new(&distances_m, &void).delta_h_m.is_nan());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let tile_source = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let profile = Profile::builder()
+            .start(Coord {
+                x: -71.308_307_164_413_69,
+                y: 44.283_098_066_031_65,
+            })
+            .max_step(90.0)
+            .end(Coord {
+                x: -71.297_207_328_376_8,
+                y: 44.256_280_984_242_78,
+            })
+            .earth_curve(true)
+            .build(&tile_source)
+            .unwrap();
+        let json = serde_json::to_string(&profile).unwrap();
+        let decoded: Profile<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(profile, decoded);
+    }
+
+    #[test]
+    fn test_amsl_altitudes() {
+        let start = Coord {
+            x: -71.308_307_164_413_69,
+            y: 44.283_098_066_031_65,
+        };
+        let end = Coord {
+            x: -71.297_207_328_376_8,
+            y: 44.256_280_984_242_78,
+        };
+        let tile_source = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let builder = Profile::builder()
+            .start(start)
+            .max_step(90.0)
+            .end(end)
+            .earth_curve(true);
+
+        let agl = builder
+            .clone()
+            .start_alt(10.0)
+            .end_alt(2.0)
+            .build(&tile_source)
+            .unwrap();
+        let start_ground_m = agl.start_height.amsl_m - 10.0;
+        let end_ground_m = agl.end_height.amsl_m - 2.0;
+        assert_eq!(agl.start_height.agl_m, 10.0);
+        assert_eq!(agl.end_height.agl_m, 2.0);
+
+        let amsl = builder
+            .clone()
+            .start_alt_amsl(start_ground_m + 10.0)
+            .end_alt_amsl(end_ground_m + 2.0)
+            .build(&tile_source)
+            .unwrap();
+        assert_eq!(agl, amsl);
+
+        assert!(matches!(
+            builder.end_alt_amsl(end_ground_m - 1.0).build(&tile_source),
+            Err(crate::TerrainError::BelowGround { end: "end", .. })
+        ));
+    }
+
+    #[test]
+    fn test_earth_bulge() {
+        let tile_source = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        // There are no tiles around Null Island, so the terrain is
+        // flat and at sea level.
+        let builder = Profile::builder()
+            .start(Coord { x: 0.0, y: 0.0 })
+            .start_alt(10.0)
+            .max_step(90.0)
+            .end(Coord { x: 1.0, y: 0.0 })
+            .end_alt(10.0);
+
+        let flat = builder.clone().build(&tile_source).unwrap();
+        assert!(flat.earth_bulge_m.iter().all(|bulge| *bulge == 0.0));
+
+        let curved = builder.earth_curve(true).build(&tile_source).unwrap();
+        assert_eq!(curved.terrain_elev_m, flat.terrain_elev_m);
+        assert_eq!(curved.los_elev_m, flat.los_elev_m);
+
+        let distance_m = *curved.distances_m.last().unwrap();
+        let max_bulge_m = curved
+            .earth_bulge_m
+            .iter()
+            .fold(f64::MIN, |max, bulge| max.max(*bulge));
+        approx::assert_relative_eq!(
+            max_bulge_m,
+            distance_m.powi(2) / (8.0 * crate::constants::MEAN_EARTH_RADIUS),
+            max_relative = 1e-3
+        );
+        assert_eq!(curved.earth_bulge_m[0], 0.0);
+        assert_eq!(*curved.earth_bulge_m.last().unwrap(), 0.0);
+
+        let terrain: Vec<f64> = curved.flat_earth_terrain_iter().collect();
+        assert_eq!(terrain, curved.earth_bulge_m.to_vec());
+        let los: Vec<f64> = curved.curved_los_iter().collect();
+        assert!(los
+            .iter()
+            .zip(curved.earth_bulge_m.iter())
+            .all(|(los, bulge)| *los == 10.0 - *bulge));
+    }
 }
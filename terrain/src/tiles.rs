@@ -1,6 +1,6 @@
 //! NASADEM file aggregator.
 
-use crate::TerrainError;
+use crate::{Geoid, TerrainError};
 use dashmap::DashMap;
 use geo::geometry::Coord;
 use log::debug;
@@ -24,6 +24,12 @@ pub struct Tiles {
 
     /// Tiles which have been loaded on demand.
     tiles: DashMap<Coord<i16>, Arc<Tile>>,
+
+    /// Geoid model for converting between vertical datums.
+    geoid: Option<Arc<Geoid>>,
+
+    /// Report heights above the ellipsoid instead of the geoid.
+    ellipsoidal: bool,
 }
 
 impl Tiles {
@@ -46,6 +52,8 @@ impl Tiles {
                 tile_dir,
                 tile_mode,
                 tiles,
+                geoid: None,
+                ellipsoidal: false,
             })
         } else {
             Err(TerrainError::Path(tile_dir))
@@ -56,6 +64,11 @@ impl Tiles {
     ///
     /// `Tiles` will attempt to fetch the tile from disk if it doesn't
     /// already have it in memory.
+    ///
+    /// Longitudes outside of [-180, 180) are wrapped and latitudes
+    /// clamped to [-90, 90] first, so `coord` should be
+    /// [normalized](normalize) before looking it up in the returned
+    /// tile.
     pub fn get(&self, coord: Coord<C>) -> Result<Arc<Tile>, TerrainError> {
         let sw_corner = sw_corner(coord);
         self.tiles
@@ -71,6 +84,74 @@ impl Tiles {
             })
             .map(|r| r.clone())
     }
+
+    /// Attaches `geoid` so ellipsoidal endpoint altitudes can be
+    /// converted, while heights remain orthometric.
+    #[must_use]
+    pub fn with_geoid(mut self, geoid: Arc<Geoid>) -> Self {
+        self.geoid = Some(geoid);
+        self.ellipsoidal = false;
+        self
+    }
+
+    /// Reports heights above the WGS84 ellipsoid, rather than the
+    /// EGM96 geoid NASADEM is relative to, by adding `geoid`'s
+    /// undulation to every sample.
+    ///
+    /// This applies to profiles and viewsheds built from these tiles,
+    /// but not to raw [`Tile`]s returned by [`Tiles::get`].
+    #[must_use]
+    pub fn ellipsoidal(mut self, geoid: Arc<Geoid>) -> Self {
+        self.geoid = Some(geoid);
+        self.ellipsoidal = true;
+        self
+    }
+
+    /// Returns the attached geoid, if any.
+    pub fn geoid(&self) -> Option<&Geoid> {
+        self.geoid.as_deref()
+    }
+
+    /// Returns `true` if heights are reported above the ellipsoid.
+    pub fn is_ellipsoidal(&self) -> bool {
+        self.ellipsoidal
+    }
+
+    /// Returns the height at `coord` in this source's vertical datum.
+    pub fn elevation_m(&self, coord: Coord<C>) -> Result<C, TerrainError> {
+        let coord = normalize(coord);
+        let elevation = C::from(self.get(coord)?.get_unchecked(coord));
+        Ok(elevation + self.datum_offset_m(coord)?)
+    }
+
+    /// Returns the height at `coord` in this source's vertical datum,
+    /// reusing `tile` if it contains `coord` or replacing it with the
+    /// one which does.
+    ///
+    /// Walking neighboring coordinates this way avoids a map lookup
+    /// for most samples.
+    pub(crate) fn sample_m(
+        &self,
+        tile: &mut Arc<Tile>,
+        coord: Coord<C>,
+    ) -> Result<C, TerrainError> {
+        let coord = normalize(coord);
+        let elevation = if let Some(elevation) = tile.get(coord) {
+            elevation
+        } else {
+            *tile = self.get(coord)?;
+            tile.get_unchecked(coord)
+        };
+        Ok(C::from(elevation) + self.datum_offset_m(coord)?)
+    }
+
+    /// Returns the offset to add to raw tile heights at `coord`.
+    pub(crate) fn datum_offset_m(&self, coord: Coord<C>) -> Result<C, TerrainError> {
+        match &self.geoid {
+            Some(geoid) if self.ellipsoidal => geoid.try_undulation_m(coord),
+            _ => Ok(0.0),
+        }
+    }
 }
 
 /// Private API.
@@ -115,12 +196,31 @@ pub enum TileMode {
     MemMap,
 }
 
+/// Returns `coord` with its longitude wrapped into [-180, 180) and
+/// its latitude clamped to [-90, 90].
+pub(crate) fn normalize(Coord { x, y }: Coord<C>) -> Coord<C> {
+    let x = if (-180.0..180.0).contains(&x) {
+        x
+    } else {
+        (x + 180.0).rem_euclid(360.0) - 180.0
+    };
+    Coord {
+        x,
+        y: y.clamp(-90.0, 90.0),
+    }
+}
+
 /// Returns the southwest corner as integers for coord.
-fn sw_corner(Coord { x, y }: Coord<C>) -> Coord<i16> {
+///
+/// The 180th meridian is served by the westernmost tiles' first
+/// column, and the poles by the polar tiles' outermost row.
+fn sw_corner(coord: Coord<C>) -> Coord<i16> {
+    let Coord { x, y } = normalize(coord);
+    // Rounding in `normalize` may leave a longitude of exactly 180.
     #[allow(clippy::cast_possible_truncation)]
     Coord {
-        x: (x.floor() as i16),
-        y: (y.floor() as i16),
+        x: (x.floor().min(179.0) as i16),
+        y: (y.floor().min(89.0) as i16),
     }
 }
 
@@ -142,6 +242,30 @@ fn file_name(Coord { x, y }: Coord<i16>) -> String {
 #[cfg(test)]
 mod tests {
     use super::{file_name, sw_corner, Coord, TileMode, Tiles};
+    use crate::Profile;
+    use std::path::PathBuf;
+
+    /// A directory of synthetic 3-arcsecond tiles, each filled with
+    /// a single elevation, which is removed on drop.
+    struct SyntheticTiles(PathBuf);
+
+    impl SyntheticTiles {
+        fn new(name: &str, tiles: &[(&str, i16)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("terrain-{name}-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            for (file_name, elevation) in tiles {
+                let samples = elevation.to_be_bytes().repeat(1201 * 1201);
+                std::fs::write(dir.join(file_name), samples).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for SyntheticTiles {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
 
     const MT_WASHINGTON: Coord = Coord {
         y: 44.2705,
@@ -165,6 +289,88 @@ mod tests {
         assert_eq!(tile.get_unchecked(MT_WASHINGTON), 1903);
     }
 
+    #[test]
+    fn test_sw_corner_wraps() {
+        let corner = |x, y| {
+            let Coord { x, y } = sw_corner(Coord { x, y });
+            (x, y)
+        };
+        assert_eq!(corner(179.5, 0.5), (179, 0));
+        assert_eq!(corner(180.0, 0.5), (-180, 0));
+        assert_eq!(corner(-180.0, 0.5), (-180, 0));
+        assert_eq!(corner(180.5, 0.5), (-180, 0));
+        assert_eq!(corner(-180.5, 0.5), (179, 0));
+        assert_eq!(corner(541.5, 0.5), (-179, 0));
+        assert_eq!(corner(0.5, 90.0), (0, 89));
+        assert_eq!(corner(0.5, 91.0), (0, 89));
+        assert_eq!(corner(0.5, -90.0), (0, -90));
+        assert_eq!(corner(0.5, -91.0), (0, -90));
+    }
+
+    #[test]
+    fn test_antimeridian_and_poles() {
+        let dir = SyntheticTiles::new(
+            "antimeridian",
+            &[
+                ("N00E179.hgt", 100),
+                ("N00W180.hgt", 200),
+                ("N89E000.hgt", 300),
+                ("S90E000.hgt", 400),
+            ],
+        );
+        let tiles = Tiles::new(dir.0.clone(), TileMode::InMem).unwrap();
+        let elevation = |x, y| tiles.elevation_m(Coord { x, y }).unwrap();
+
+        assert_eq!(elevation(179.5, 0.5), 100.0);
+        assert_eq!(elevation(-179.5, 0.5), 200.0);
+        assert_eq!(elevation(180.0, 0.5), 200.0);
+        assert_eq!(elevation(-180.0, 0.5), 200.0);
+        assert_eq!(elevation(180.5, 0.5), 200.0);
+        assert_eq!(elevation(-180.5, 0.5), 100.0);
+        assert_eq!(elevation(0.5, 90.0), 300.0);
+        assert_eq!(elevation(0.5, -90.0), 400.0);
+
+        // East to west across the antimeridian.
+        let profile = Profile::<f64>::builder()
+            .start(Coord { x: 179.9, y: 0.5 })
+            .max_step(90.0)
+            .end(Coord { x: -179.9, y: 0.5 })
+            .build(&tiles)
+            .unwrap();
+        let distance_m = *profile.distances_m.last().unwrap();
+        assert!((22_000.0..22_500.0).contains(&distance_m));
+        // The 180th meridian itself is served by W180.
+        let crossing = profile
+            .great_circle
+            .iter()
+            .position(|point| !(0.0..180.0).contains(&point.x()))
+            .unwrap();
+        assert!(profile.terrain_elev_m[..crossing]
+            .iter()
+            .all(|elev| *elev == 100.0));
+        assert!(profile.terrain_elev_m[crossing..]
+            .iter()
+            .all(|elev| *elev == 200.0));
+
+        // Up to, and down from, the poles.
+        for (start, end, expected) in [
+            (Coord { x: 0.5, y: 89.5 }, Coord { x: 0.5, y: 90.0 }, 300.0),
+            (
+                Coord { x: 0.5, y: -89.5 },
+                Coord { x: 0.5, y: -90.0 },
+                400.0,
+            ),
+        ] {
+            let profile = Profile::<f64>::builder()
+                .start(start)
+                .max_step(90.0)
+                .end(end)
+                .build(&tiles)
+                .unwrap();
+            assert!(profile.terrain_elev_m.iter().all(|elev| *elev == expected));
+        }
+    }
+
     #[test]
     fn test_file_name() {
         let name = file_name(sw_corner(Coord {
@@ -0,0 +1,740 @@
+//! Visibility of the terrain surrounding a site.
+
+use crate::{constants::MEAN_EARTH_RADIUS, tiles::C, TerrainError, Tiles};
+use geo::{
+    algorithm::{Contains, HaversineDistance},
+    geometry::{Coord, LineString, MultiPolygon, Point, Polygon},
+};
+use std::collections::{HashMap, HashSet};
+
+pub(crate) const ARCSEC_PER_DEG: C = 3600.0;
+
+/// Raster of DEM cells visible from a site.
+///
+/// Cells are aligned with, and the same size as, the underlying DEM
+/// samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Viewshed {
+    /// Grid the mask is laid out on.
+    grid: Grid,
+
+    /// Per-cell visibility, row major starting with the southernmost
+    /// row.
+    visible: Box<[bool]>,
+}
+
+impl Viewshed {
+    pub fn builder() -> ViewshedBuilder {
+        ViewshedBuilder {
+            center: None,
+            alt_m: 0.0,
+            target_alt_m: 0.0,
+            radius_m: None,
+            earth_curve: false,
+            earth_radius: MEAN_EARTH_RADIUS,
+        }
+    }
+
+    /// Returns the number of (columns, rows) in this viewshed.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.grid.cols, self.grid.rows)
+    }
+
+    /// Returns the _center_ of the southwest most cell.
+    pub fn sw_corner_center(&self) -> Coord<C> {
+        self.grid.sw_corner_center
+    }
+
+    /// Returns the width and height of each cell in degrees.
+    pub fn cell_size_deg(&self) -> C {
+        self.grid.cell_deg
+    }
+
+    /// Returns the visibility mask, row major starting with the
+    /// southernmost row.
+    pub fn mask(&self) -> &[bool] {
+        &self.visible
+    }
+
+    /// Returns the number of visible cells.
+    pub fn visible_cells(&self) -> usize {
+        self.visible.iter().filter(|visible| **visible).count()
+    }
+
+    /// Returns whether the cell containing `coord` is visible, or
+    /// `None` if `coord` is outside the raster.
+    pub fn get(&self, coord: Coord<C>) -> Option<bool> {
+        self.grid
+            .coord_to_xy(coord)
+            .map(|xy| self.visible[self.grid.xy_to_linear_index(xy)])
+    }
+
+    /// Returns the visible area as polygons.
+    ///
+    /// Cells touching only at a corner are considered disconnected.
+    pub fn to_multipolygon(&self) -> MultiPolygon<C> {
+        polygonize(&self.grid, &self.visible)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ViewshedBuilder {
+    /// Location of the observer (required).
+    center: Option<Coord<C>>,
+
+    /// Observer altitude above ground (meters, defaults to 0).
+    alt_m: C,
+
+    /// Target altitude above ground (meters, defaults to 0).
+    target_alt_m: C,
+
+    /// Radius around `center` to compute visibility for (required).
+    radius_m: Option<C>,
+
+    /// Add earth curvature (defaults to false).
+    earth_curve: bool,
+
+    /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    earth_radius: C,
+}
+
+impl ViewshedBuilder {
+    /// Location of the observer (required).
+    #[must_use]
+    pub fn center(mut self, coord: Coord<C>) -> Self {
+        self.center = Some(coord);
+        self
+    }
+
+    /// Observer altitude above ground (meters, defaults to 0).
+    #[must_use]
+    pub fn alt(mut self, meters: C) -> Self {
+        self.alt_m = meters;
+        self
+    }
+
+    /// Target altitude above ground (meters, defaults to 0).
+    ///
+    /// A cell is visible when a point this high above it can be seen
+    /// from the observer.
+    #[must_use]
+    pub fn target_alt(mut self, meters: C) -> Self {
+        self.target_alt_m = meters;
+        self
+    }
+
+    /// Radius around `center` to compute visibility for (required).
+    #[must_use]
+    pub fn radius(mut self, meters: C) -> Self {
+        self.radius_m = Some(meters);
+        self
+    }
+
+    /// Add earth curvature (defaults to false).
+    #[must_use]
+    pub fn earth_curve(mut self, add_curve: bool) -> Self {
+        self.earth_curve = add_curve;
+        self
+    }
+
+    /// Earth radius, defaults to [`MEAN_EARTH_RADIUS`].
+    #[must_use]
+    pub fn earth_radius(mut self, earth_radius_m: C) -> Self {
+        self.earth_radius = earth_radius_m;
+        self
+    }
+
+    pub fn build(&self, tiles: &Tiles) -> Result<Viewshed, TerrainError> {
+        let center = self.center.ok_or(TerrainError::Builder("center"))?;
+        let radius_m = self.radius_m.ok_or(TerrainError::Builder("radius"))?;
+
+        let cell_deg = C::from(tiles.get(center)?.resolution()) / ARCSEC_PER_DEG;
+        let grid = Grid::around(center, radius_m, cell_deg)?;
+        let elevation = grid.elevations(tiles)?;
+        let visible = self.r2(&grid, &elevation, tiles)?;
+
+        Ok(Viewshed { grid, visible })
+    }
+}
+
+/// Private API.
+impl ViewshedBuilder {
+    /// Returns the visibility of every cell in `grid` from this
+    /// builder's site.
+    pub(crate) fn r2(
+        &self,
+        grid: &Grid,
+        elevation: &[C],
+        tiles: &Tiles,
+    ) -> Result<Box<[bool]>, TerrainError> {
+        let center = self.center.ok_or(TerrainError::Builder("center"))?;
+        let radius_m = self.radius_m.ok_or(TerrainError::Builder("radius"))?;
+        let observer = grid
+            .coord_to_xy(center)
+            .ok_or(TerrainError::Builder("center"))?;
+        let observer_elev_m = tiles.elevation_m(center)? + self.alt_m;
+
+        let mut visible = vec![false; grid.cols * grid.rows].into_boxed_slice();
+        visible[grid.xy_to_linear_index(observer)] = true;
+
+        // Only cells within `radius_m` of the observer can be visible,
+        // so there is no need to look beyond the window around it.
+        let (window_min, window_max) = grid.window(&Grid::around(center, radius_m, grid.cell_deg)?);
+
+        // Returns the distance to, and terrain height relative to the
+        // observer (including the drop due to earth curvature) of,
+        // cell `idx`.
+        let center_point = Point::from(center);
+        let distance_height = |idx: usize| {
+            let coord = grid.xy_to_coord(grid.linear_index_to_xy(idx));
+            let distance_m = center_point.haversine_distance(&Point::from(coord));
+            let bulge_m = if self.earth_curve {
+                distance_m * distance_m / (2.0 * self.earth_radius)
+            } else {
+                0.0
+            };
+            (distance_m, elevation[idx] - bulge_m - observer_elev_m)
+        };
+
+        // R2: cast a ray from the observer to every cell on the
+        // window's perimeter, marking cells along the way which are
+        // not hidden by any cell closer to the observer on the same
+        // ray.
+        //
+        // Franklin, W. R., & Ray, C. (1994). Higher isn’t
+        // necessarily better: Visibility algorithms and experiments.
+        for perimeter in Grid::perimeter(window_min, window_max) {
+            #[allow(clippy::cast_possible_wrap)]
+            let (dx, dy) = (
+                perimeter.0 as isize - observer.0 as isize,
+                perimeter.1 as isize - observer.1 as isize,
+            );
+            let steps = dx.abs().max(dy.abs());
+            let mut max_slope = C::NEG_INFINITY;
+            for step in 1..=steps {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_precision_loss,
+                    clippy::cast_sign_loss
+                )]
+                let xy = (
+                    (observer.0 as C + (dx * step) as C / steps as C).round() as usize,
+                    (observer.1 as C + (dy * step) as C / steps as C).round() as usize,
+                );
+                let idx = grid.xy_to_linear_index(xy);
+                let (distance_m, height_m) = distance_height(idx);
+                if distance_m > radius_m {
+                    break;
+                }
+                if (height_m + self.target_alt_m) / distance_m >= max_slope {
+                    visible[idx] = true;
+                }
+                max_slope = max_slope.max(height_m / distance_m);
+            }
+        }
+
+        Ok(visible)
+    }
+}
+
+/// A raster aligned with DEM samples.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Grid {
+    /// Center of the southwest most cell.
+    pub(crate) sw_corner_center: Coord<C>,
+
+    /// Width and height of a cell in degrees.
+    pub(crate) cell_deg: C,
+
+    /// Number of columns.
+    pub(crate) cols: usize,
+
+    /// Number of rows.
+    pub(crate) rows: usize,
+}
+
+impl Grid {
+    /// Returns a grid with cells `cell_deg` degrees wide, covering at
+    /// least `radius_m` in every direction from `center`.
+    ///
+    /// Cell centers are always integer multiples of `cell_deg`, so
+    /// grids with the same cell size line up with each other and
+    /// with DEM samples.
+    ///
+    /// Meridians converge towards the poles, so returns an error if
+    /// covering `radius_m` would take more than 360° of longitude.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub(crate) fn around(center: Coord<C>, radius_m: C, cell_deg: C) -> Result<Self, TerrainError> {
+        let m_per_deg = MEAN_EARTH_RADIUS.to_radians();
+        let half_rows = (radius_m / (cell_deg * m_per_deg)).ceil() as usize;
+        let half_cols =
+            (radius_m / (cell_deg * m_per_deg * center.y.to_radians().cos().abs())).ceil();
+        let width_deg = (2.0 * half_cols + 1.0) * cell_deg;
+        if width_deg.is_nan() || width_deg > 360.0 {
+            return Err(TerrainError::NearPole { center, radius_m });
+        }
+        let half_cols = half_cols as usize;
+        #[allow(clippy::cast_precision_loss)]
+        let sw_corner_center = Coord {
+            x: ((center.x / cell_deg).round() - half_cols as C) * cell_deg,
+            y: ((center.y / cell_deg).round() - half_rows as C) * cell_deg,
+        };
+        Ok(Self {
+            sw_corner_center,
+            cell_deg,
+            cols: 2 * half_cols + 1,
+            rows: 2 * half_rows + 1,
+        })
+    }
+
+    /// Returns the smallest grid covering both `self` and `other`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        debug_assert_eq!(self.cell_deg, other.cell_deg);
+        let cell_deg = self.cell_deg;
+        // Work in whole cells to avoid accumulating rounding error.
+        let extent = |grid: &Self| {
+            let x = (grid.sw_corner_center.x / cell_deg).round() as i64;
+            let y = (grid.sw_corner_center.y / cell_deg).round() as i64;
+            (x, y, x + grid.cols as i64, y + grid.rows as i64)
+        };
+        let (a_w, a_s, a_e, a_n) = extent(self);
+        let (b_w, b_s, b_e, b_n) = extent(other);
+        let (w, s, e, n) = (a_w.min(b_w), a_s.min(b_s), a_e.max(b_e), a_n.max(b_n));
+        #[allow(clippy::cast_sign_loss)]
+        Self {
+            sw_corner_center: Coord {
+                x: w as C * cell_deg,
+                y: s as C * cell_deg,
+            },
+            cell_deg,
+            cols: (e - w) as usize,
+            rows: (n - s) as usize,
+        }
+    }
+
+    /// Returns the lowest and highest `(x, y)` of the cells `other`
+    /// shares with `self`.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub(crate) fn window(&self, other: &Self) -> ((usize, usize), (usize, usize)) {
+        let offset_x =
+            ((other.sw_corner_center.x - self.sw_corner_center.x) / self.cell_deg).round() as isize;
+        let offset_y =
+            ((other.sw_corner_center.y - self.sw_corner_center.y) / self.cell_deg).round() as isize;
+        let clamp_x = |x: isize| x.clamp(0, self.cols as isize - 1) as usize;
+        let clamp_y = |y: isize| y.clamp(0, self.rows as isize - 1) as usize;
+        (
+            (clamp_x(offset_x), clamp_y(offset_y)),
+            (
+                clamp_x(offset_x + other.cols as isize - 1),
+                clamp_y(offset_y + other.rows as isize - 1),
+            ),
+        )
+    }
+
+    /// Returns the center of cell `(x, y)`.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn xy_to_coord(&self, (x, y): (usize, usize)) -> Coord<C> {
+        Coord {
+            x: self.sw_corner_center.x + x as C * self.cell_deg,
+            y: self.sw_corner_center.y + y as C * self.cell_deg,
+        }
+    }
+
+    /// Returns the cell containing `coord`, if any.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub(crate) fn coord_to_xy(&self, coord: Coord<C>) -> Option<(usize, usize)> {
+        let x = ((coord.x - self.sw_corner_center.x) / self.cell_deg).round();
+        let y = ((coord.y - self.sw_corner_center.y) / self.cell_deg).round();
+        if 0.0 <= x && x < self.cols as C && 0.0 <= y && y < self.rows as C {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn xy_to_linear_index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.cols + x
+    }
+
+    pub(crate) fn linear_index_to_xy(&self, idx: usize) -> (usize, usize) {
+        (idx % self.cols, idx / self.cols)
+    }
+
+    /// Returns the elevation at every cell's center.
+    pub(crate) fn elevations(&self, tiles: &Tiles) -> Result<Box<[C]>, TerrainError> {
+        let mut elevation = Vec::with_capacity(self.cols * self.rows);
+        let mut tile = tiles.get(self.sw_corner_center)?;
+        for idx in 0..(self.cols * self.rows) {
+            let coord = self.xy_to_coord(self.linear_index_to_xy(idx));
+            elevation.push(tiles.sample_m(&mut tile, coord)?);
+        }
+        Ok(elevation.into_boxed_slice())
+    }
+
+    /// Returns every cell on the edge of the rectangle spanning
+    /// `min` to `max`.
+    pub(crate) fn perimeter(
+        (min_x, min_y): (usize, usize),
+        (max_x, max_y): (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let south = (min_x..max_x).map(move |x| (x, min_y));
+        let east = (min_y..max_y).map(move |y| (max_x, y));
+        let north = ((min_x + 1)..=max_x).rev().map(move |x| (x, max_y));
+        let west = ((min_y + 1)..=max_y).rev().map(move |y| (min_x, y));
+        south.chain(east).chain(north).chain(west)
+    }
+
+    /// Returns the corner shared by cells `(x - 1, y - 1)` and `(x,
+    /// y)`.
+    #[allow(clippy::cast_precision_loss)]
+    fn vertex_to_coord(&self, (x, y): (usize, usize)) -> Coord<C> {
+        Coord {
+            x: self.sw_corner_center.x + (x as C - 0.5) * self.cell_deg,
+            y: self.sw_corner_center.y + (y as C - 0.5) * self.cell_deg,
+        }
+    }
+}
+
+/// Edge direction used when tracing cell outlines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Heading {
+    East,
+    North,
+    West,
+    South,
+}
+
+impl Heading {
+    fn left(self) -> Self {
+        match self {
+            Self::East => Self::North,
+            Self::North => Self::West,
+            Self::West => Self::South,
+            Self::South => Self::East,
+        }
+    }
+
+    fn right(self) -> Self {
+        self.left().left().left()
+    }
+
+    fn step(self, (x, y): (usize, usize)) -> (usize, usize) {
+        match self {
+            Self::East => (x + 1, y),
+            Self::North => (x, y + 1),
+            Self::West => (x - 1, y),
+            Self::South => (x, y - 1),
+        }
+    }
+}
+
+/// Traces the outlines of `true` cells in `mask` and returns them as
+/// polygons.
+///
+/// Outlines are walked with the masked region on the left, so
+/// exteriors come out counter-clockwise and holes clockwise. Where
+/// two outlines meet at a single vertex we always turn left, which
+/// keeps diagonally adjacent cells in separate rings.
+pub(crate) fn polygonize(grid: &Grid, mask: &[bool]) -> MultiPolygon<C> {
+    let is_set = |x: Option<usize>, y: Option<usize>| match (x, y) {
+        (Some(x), Some(y)) if x < grid.cols && y < grid.rows => {
+            mask[grid.xy_to_linear_index((x, y))]
+        }
+        _ => false,
+    };
+
+    // Every cell edge separating a masked cell from an unmasked one,
+    // keyed by its starting vertex.
+    let mut edges: Vec<((usize, usize), Heading)> = Vec::new();
+    for (idx, _) in mask.iter().enumerate().filter(|(_, set)| **set) {
+        let (x, y) = grid.linear_index_to_xy(idx);
+        if !is_set(Some(x), y.checked_sub(1)) {
+            edges.push(((x, y), Heading::East));
+        }
+        if !is_set(Some(x + 1), Some(y)) {
+            edges.push(((x + 1, y), Heading::North));
+        }
+        if !is_set(Some(x), Some(y + 1)) {
+            edges.push(((x + 1, y + 1), Heading::West));
+        }
+        if !is_set(x.checked_sub(1), Some(y)) {
+            edges.push(((x, y + 1), Heading::South));
+        }
+    }
+    let mut unused: HashSet<((usize, usize), Heading)> = edges.iter().copied().collect();
+
+    let mut exteriors: Vec<LineString<C>> = Vec::new();
+    let mut holes: Vec<(LineString<C>, Coord<C>)> = Vec::new();
+
+    for &start in &edges {
+        if !unused.remove(&start) {
+            continue;
+        }
+        let mut ring = vec![grid.vertex_to_coord(start.0)];
+        let mut signed_area2: isize = 0;
+        let (mut vertex, mut heading) = start;
+        loop {
+            let next_vertex = heading.step(vertex);
+            #[allow(clippy::cast_possible_wrap)]
+            {
+                signed_area2 += vertex.0 as isize * next_vertex.1 as isize
+                    - next_vertex.0 as isize * vertex.1 as isize;
+            }
+            vertex = next_vertex;
+            let next = [heading.left(), heading, heading.right()]
+                .into_iter()
+                .map(|candidate| (vertex, candidate))
+                .find(|edge| *edge == start || unused.contains(edge))
+                .expect("cell outlines are always closed");
+            if next == start {
+                break;
+            }
+            if next.1 != heading {
+                ring.push(grid.vertex_to_coord(vertex));
+            }
+            unused.remove(&next);
+            heading = next.1;
+        }
+        ring.push(ring[0]);
+        let ring = LineString::from(ring);
+
+        if signed_area2 > 0 {
+            exteriors.push(ring);
+        } else {
+            // The cell to the right of a hole's edge is unmasked and
+            // lies strictly inside the hole.
+            let (x, y) = start.0;
+            let inside = grid.xy_to_coord(match start.1 {
+                Heading::East => (x, y - 1),
+                Heading::North => (x, y),
+                Heading::West => (x - 1, y),
+                Heading::South => (x - 1, y - 1),
+            });
+            holes.push((ring, inside));
+        }
+    }
+
+    let mut polygons: Vec<Polygon<C>> = exteriors
+        .into_iter()
+        .map(|exterior| Polygon::new(exterior, vec![]))
+        .collect();
+
+    // Assign each hole to the smallest exterior containing it.
+    let mut interiors: HashMap<usize, Vec<LineString<C>>> = HashMap::new();
+    for (hole, inside) in holes {
+        let parent = polygons
+            .iter()
+            .enumerate()
+            .filter(|(_, polygon)| polygon.contains(&inside))
+            .min_by(|(_, a), (_, b)| ring_area(a.exterior()).total_cmp(&ring_area(b.exterior())))
+            .map(|(idx, _)| idx)
+            .expect("holes are always inside an exterior");
+        interiors.entry(parent).or_default().push(hole);
+    }
+    for (idx, holes) in interiors {
+        let polygon = &mut polygons[idx];
+        for hole in holes {
+            polygon.interiors_push(hole);
+        }
+    }
+
+    MultiPolygon::new(polygons)
+}
+
+/// Returns the unsigned area enclosed by `ring` in square degrees.
+fn ring_area(ring: &LineString<C>) -> C {
+    ring.lines()
+        .map(|line| line.start.x * line.end.y - line.end.x * line.start.y)
+        .sum::<C>()
+        .abs()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{polygonize, Grid, Viewshed};
+    use crate::{TerrainError, TileMode, Tiles};
+    use geo::{
+        algorithm::{Area, HaversineDistance},
+        geometry::{Coord, Point},
+    };
+
+    /// Somewhere in the Gulf of Guinea, where there are no tiles and
+    /// the earth is perfectly flat.
+    const NULL_ISLAND: Coord = Coord { x: 0.0, y: 0.0 };
+
+    const MT_WASHINGTON: Coord = Coord {
+        y: 44.2705,
+        x: -71.30325,
+    };
+
+    /// Pinkham Notch, in the valley east of Mt Washington.
+    const PINKHAM_NOTCH: Coord = Coord {
+        y: 44.2572,
+        x: -71.2531,
+    };
+
+    fn grid(cols: usize, rows: usize) -> Grid {
+        Grid {
+            sw_corner_center: Coord { x: 0.5, y: 0.5 },
+            cell_deg: 1.0,
+            cols,
+            rows,
+        }
+    }
+
+    #[test]
+    fn test_polygonize_ring() {
+        #[rustfmt::skip]
+        let mask = [
+            true, true,  true,
+            true, false, true,
+            true, true,  true,
+        ];
+        let multipolygon = polygonize(&grid(3, 3), &mask);
+        assert_eq!(multipolygon.0.len(), 1);
+        let polygon = &multipolygon.0[0];
+        assert_eq!(polygon.interiors().len(), 1);
+        assert_eq!(polygon.exterior().0.len(), 5);
+        assert_eq!(polygon.unsigned_area(), 8.0);
+    }
+
+    #[test]
+    fn test_polygonize_diagonal() {
+        #[rustfmt::skip]
+        let mask = [
+            true,  false,
+            false, true,
+        ];
+        let multipolygon = polygonize(&grid(2, 2), &mask);
+        assert_eq!(multipolygon.0.len(), 2);
+        assert!(multipolygon.0.iter().all(|p| p.interiors().is_empty()));
+        assert_eq!(multipolygon.unsigned_area(), 2.0);
+    }
+
+    #[test]
+    fn test_polygonize_island_in_hole() {
+        #[rustfmt::skip]
+        let mask = [
+            true, true,  true,  true,  true,
+            true, false, false, false, true,
+            true, false, true,  false, true,
+            true, false, false, false, true,
+            true, true,  true,  true,  true,
+        ];
+        let multipolygon = polygonize(&grid(5, 5), &mask);
+        assert_eq!(multipolygon.0.len(), 2);
+        let holes: Vec<usize> = multipolygon.0.iter().map(|p| p.interiors().len()).collect();
+        assert!(holes == [1, 0] || holes == [0, 1]);
+        assert_eq!(multipolygon.unsigned_area(), 17.0);
+    }
+
+    #[test]
+    fn test_flat_earth_sees_everything() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let viewshed = Viewshed::builder()
+            .center(NULL_ISLAND)
+            .alt(2.0)
+            .radius(2_000.0)
+            .build(&tiles)
+            .unwrap();
+        let (cols, rows) = viewshed.dimensions();
+        assert_eq!(cols % 2, 1);
+        assert_eq!(rows % 2, 1);
+        let center = Point::from(NULL_ISLAND);
+        for (idx, visible) in viewshed.mask().iter().enumerate() {
+            let coord = viewshed
+                .grid
+                .xy_to_coord(viewshed.grid.linear_index_to_xy(idx));
+            let distance_m = center.haversine_distance(&Point::from(coord));
+            assert_eq!(*visible, distance_m <= 2_000.0, "{coord:?}");
+        }
+        let multipolygon = viewshed.to_multipolygon();
+        assert_eq!(multipolygon.0.len(), 1);
+        assert!(multipolygon.0[0].interiors().is_empty());
+    }
+
+    #[test]
+    fn test_radio_horizon() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let alt_m = 2.0;
+        let viewshed = Viewshed::builder()
+            .center(NULL_ISLAND)
+            .alt(alt_m)
+            .radius(10_000.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap();
+
+        // Distance to the horizon over a smooth sphere.
+        let horizon_m = (2.0 * crate::constants::MEAN_EARTH_RADIUS * alt_m).sqrt();
+        let center = Point::from(NULL_ISLAND);
+        for (idx, visible) in viewshed.mask().iter().enumerate() {
+            let coord = viewshed
+                .grid
+                .xy_to_coord(viewshed.grid.linear_index_to_xy(idx));
+            let distance_m = center.haversine_distance(&Point::from(coord));
+            if distance_m < horizon_m - 100.0 {
+                assert!(visible, "{coord:?}");
+            } else if distance_m > horizon_m + 100.0 {
+                assert!(!visible, "{coord:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_valley_sees_summit() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let viewshed = Viewshed::builder()
+            .center(PINKHAM_NOTCH)
+            .alt(2.0)
+            .target_alt(10.0)
+            .radius(8_000.0)
+            .earth_curve(true)
+            .build(&tiles)
+            .unwrap();
+        assert_eq!(viewshed.get(PINKHAM_NOTCH), Some(true));
+        assert_eq!(viewshed.get(MT_WASHINGTON), Some(true));
+        assert_eq!(viewshed.get(Coord { x: -70.0, y: 44.0 }), None);
+        // Being in a notch, most of the surrounding area is hidden.
+        let (cols, rows) = viewshed.dimensions();
+        assert!(viewshed.visible_cells() < cols * rows / 2);
+    }
+
+    #[test]
+    fn test_near_pole() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let builder = Viewshed::builder().alt(2.0).radius(200.0);
+
+        // Meridians are 175 m apart here, so the grid is wide, but
+        // still well short of wrapping around the pole.
+        let viewshed = builder
+            .clone()
+            .center(Coord { x: 0.0, y: 89.9 })
+            .build(&tiles)
+            .unwrap();
+        let (cols, rows) = viewshed.dimensions();
+        assert!(cols > 100 * rows);
+        #[allow(clippy::cast_precision_loss)]
+        let width_deg = cols as f64 * viewshed.cell_size_deg();
+        assert!(width_deg < 360.0);
+        assert_eq!(viewshed.get(Coord { x: 0.0, y: 89.9 }), Some(true));
+
+        for y in [89.9999, 90.0, -90.0] {
+            assert!(matches!(
+                builder.clone().center(Coord { x: 0.0, y }).build(&tiles),
+                Err(TerrainError::NearPole { .. })
+            ));
+        }
+    }
+}
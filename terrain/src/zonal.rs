@@ -0,0 +1,408 @@
+//! Elevation statistics over geographic regions.
+
+use crate::{
+    constants::MEAN_EARTH_RADIUS, tiles::C, viewshed::ARCSEC_PER_DEG, TerrainError, Tiles,
+};
+use geo::{
+    algorithm::{Area, BooleanOps, BoundingRect, Contains, Intersects, MapCoords},
+    geometry::{Coord, LineString, Polygon, Rect},
+};
+use rayon::prelude::*;
+
+/// Elevation statistics over the DEM samples in a region.
+///
+/// Every sample contributes in proportion to how much of its cell
+/// lies within the region, so cells straddling the region's outline,
+/// or the seam between two tiles, are neither dropped nor counted
+/// twice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZonalStats {
+    /// Lowest sample elevation.
+    pub min_m: C,
+
+    /// Highest sample elevation.
+    pub max_m: C,
+
+    /// Area-weighted mean elevation.
+    pub mean_m: C,
+
+    /// Area covered by samples (square meters).
+    pub area_m2: C,
+
+    /// `(elevation, weight)` of each sample, in ascending order of
+    /// elevation.
+    samples: Box<[(C, C)]>,
+}
+
+impl ZonalStats {
+    /// Computes statistics over the samples of `tiles` overlapping
+    /// `region`, a polygon in longitude/latitude.
+    ///
+    /// `region` may cross the antimeridian, with either wrapped
+    /// (179° to -179°) or continuous (179° to 181°) longitudes.
+    ///
+    /// Returns [`TerrainError::EmptyRegion`] if `region` doesn't
+    /// overlap any samples.
+    pub fn new(tiles: &Tiles, region: &Polygon<C>) -> Result<Self, TerrainError> {
+        let region = unwrap_antimeridian(region);
+        let bounds = region.bounding_rect().ok_or(TerrainError::EmptyRegion)?;
+        let mut samples = Vec::new();
+
+        // Visit the region once per copy of the globe it overlaps,
+        // shifted back into [-180, 180]. A region narrower than 360°
+        // overlaps each tile's samples in only one of these.
+        #[allow(clippy::cast_possible_truncation)]
+        let (first, last) = (
+            ((bounds.min().x + 180.0) / 360.0).floor() as i32,
+            ((bounds.max().x + 180.0) / 360.0).floor() as i32,
+        );
+        for turn in first..=last {
+            let offset = C::from(turn) * 360.0;
+            let shifted = region.map_coords(|Coord { x, y }| Coord { x: x - offset, y });
+            Self::add_samples(tiles, &shifted, &mut samples)?;
+        }
+
+        Self::from_samples(samples)
+    }
+
+    /// Computes statistics for each of `regions` in parallel.
+    ///
+    /// Returned results are in the same order as `regions`, and
+    /// `tiles` is shared between workers so each tile is loaded at
+    /// most once.
+    pub fn par_new(tiles: &Tiles, regions: &[Polygon<C>]) -> Vec<Result<Self, TerrainError>> {
+        regions
+            .par_iter()
+            .map(|region| Self::new(tiles, region))
+            .collect()
+    }
+
+    /// Returns the number of samples overlapping the region.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Always `false`, since empty regions are an error.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the elevation below which `percent` of the region's
+    /// area lies.
+    ///
+    /// `percent` is clamped to [0, 100], so 0 is the minimum and 100
+    /// the maximum elevation.
+    pub fn percentile(&self, percent: C) -> C {
+        let total: C = self.samples.iter().map(|(_, weight)| weight).sum();
+        let target = total * percent.clamp(0.0, 100.0) / 100.0;
+        let mut cumulative = 0.0;
+        for &(elevation_m, weight) in &*self.samples {
+            cumulative += weight;
+            if cumulative >= target {
+                return elevation_m;
+            }
+        }
+        self.max_m
+    }
+
+    /// Returns the area-weighted median elevation.
+    pub fn median_m(&self) -> C {
+        self.percentile(50.0)
+    }
+}
+
+/// Private API.
+impl ZonalStats {
+    /// Appends `(elevation, weight)` for each sample overlapping
+    /// `region`, ignoring any part beyond ±180° longitude.
+    fn add_samples(
+        tiles: &Tiles,
+        region: &Polygon<C>,
+        samples: &mut Vec<(C, C)>,
+    ) -> Result<(), TerrainError> {
+        let bounds = region.bounding_rect().ok_or(TerrainError::EmptyRegion)?;
+        let (west, south) = (bounds.min().x.floor().max(-180.0), bounds.min().y.floor());
+        let (east, north) = (bounds.max().x.floor().min(179.0), bounds.max().y.floor());
+        #[allow(clippy::cast_possible_truncation)]
+        for lat in (south as i16)..=(north as i16) {
+            for lon in (west as i16)..=(east as i16) {
+                let sw_corner = Coord {
+                    x: C::from(lon),
+                    y: C::from(lat),
+                };
+                let tile = tiles.get(sw_corner)?;
+                let cell_deg = C::from(tile.resolution()) / ARCSEC_PER_DEG;
+                let half_cell = cell_deg / 2.0;
+
+                // Adjacent tiles share their edge samples; each tile
+                // only claims those on its southern and western
+                // edges.
+                let min = Coord {
+                    x: (bounds.min().x - half_cell).max(sw_corner.x),
+                    y: (bounds.min().y - half_cell).max(sw_corner.y),
+                };
+                let max = Coord {
+                    x: (bounds.max().x + half_cell).min(sw_corner.x + 1.0 - half_cell),
+                    y: (bounds.max().y + half_cell).min(sw_corner.y + 1.0 - half_cell),
+                };
+                if min.x > max.x || min.y > max.y {
+                    continue;
+                }
+
+                for sample in tile.iter_within(Rect::new(min, max)) {
+                    let cell = sample.polygon();
+                    let area_deg2 = if region.contains(&cell) {
+                        cell_deg * cell_deg
+                    } else if region.intersects(&cell) {
+                        region.intersection(&cell).unsigned_area()
+                    } else {
+                        continue;
+                    };
+                    if area_deg2 <= 0.0 {
+                        continue;
+                    }
+                    let center = sample.center();
+                    let weight = area_deg2 * center.y.to_radians().cos();
+                    let elevation_m = C::from(sample.elevation()) + tiles.datum_offset_m(center)?;
+                    samples.push((elevation_m, weight));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn from_samples(mut samples: Vec<(C, C)>) -> Result<Self, TerrainError> {
+        if samples.is_empty() {
+            return Err(TerrainError::EmptyRegion);
+        }
+        samples.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        let weight: C = samples.iter().map(|(_, weight)| weight).sum();
+        let sum: C = samples
+            .iter()
+            .map(|(elevation_m, weight)| elevation_m * weight)
+            .sum();
+        let m_per_deg = MEAN_EARTH_RADIUS.to_radians();
+        Ok(Self {
+            min_m: samples[0].0,
+            max_m: samples[samples.len() - 1].0,
+            mean_m: sum / weight,
+            area_m2: weight * m_per_deg * m_per_deg,
+            samples: samples.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(feature = "h3")]
+impl ZonalStats {
+    /// Computes statistics over `cell`'s hexagon (or pentagon).
+    pub fn from_cell(tiles: &Tiles, cell: h3o::CellIndex) -> Result<Self, TerrainError> {
+        Self::new(tiles, &cell_polygon(cell))
+    }
+
+    /// Computes statistics for each of `cells` in parallel.
+    ///
+    /// Returned results are in the same order as `cells`.
+    pub fn par_from_cells(
+        tiles: &Tiles,
+        cells: &[h3o::CellIndex],
+    ) -> Vec<Result<Self, TerrainError>> {
+        cells
+            .par_iter()
+            .map(|&cell| Self::from_cell(tiles, cell))
+            .collect()
+    }
+}
+
+/// Returns `region` with each ring's longitudes made continuous, so
+/// edges crossing the antimeridian don't span the globe.
+///
+/// For example, a ring from 179° to -179° becomes one from 179° to
+/// 181°.
+fn unwrap_antimeridian(region: &Polygon<C>) -> Polygon<C> {
+    let unwrap = |ring: &LineString<C>, mut prev_x: C| -> LineString<C> {
+        ring.coords()
+            .map(|&Coord { x, y }| {
+                let x = x + ((prev_x - x) / 360.0).round() * 360.0;
+                prev_x = x;
+                Coord { x, y }
+            })
+            .collect()
+    };
+    let Some(first) = region.exterior().0.first() else {
+        return region.clone();
+    };
+    Polygon::new(
+        unwrap(region.exterior(), first.x),
+        region
+            .interiors()
+            .iter()
+            .map(|ring| unwrap(ring, first.x))
+            .collect(),
+    )
+}
+
+/// Returns `cell`'s outline in longitude/latitude.
+#[cfg(feature = "h3")]
+fn cell_polygon(cell: h3o::CellIndex) -> Polygon<C> {
+    let exterior: Vec<Coord<C>> = cell
+        .boundary()
+        .iter()
+        .map(|vertex| Coord {
+            x: vertex.lng(),
+            y: vertex.lat(),
+        })
+        .collect();
+    Polygon::new(exterior.into(), Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZonalStats;
+    use crate::{TerrainError, TileMode, Tiles};
+    use approx::assert_relative_eq;
+    use geo::{
+        algorithm::{Area, Contains},
+        geometry::{Coord, Rect},
+        polygon,
+    };
+
+    #[test]
+    fn test_mt_washington() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let region = Rect::new(
+            Coord {
+                x: -71.31,
+                y: 44.26,
+            },
+            Coord {
+                x: -71.29,
+                y: 44.28,
+            },
+        )
+        .to_polygon();
+        let stats = ZonalStats::new(&tiles, &region).unwrap();
+
+        // Mt Washington's summit is 1917 m.
+        assert!((1880.0..=1917.0).contains(&stats.max_m));
+        assert!(stats.min_m < stats.mean_m && stats.mean_m < stats.max_m);
+        assert_relative_eq!(stats.percentile(0.0), stats.min_m);
+        assert_relative_eq!(stats.percentile(100.0), stats.max_m);
+        assert!(stats.percentile(25.0) <= stats.median_m());
+        assert!(stats.median_m() <= stats.percentile(75.0));
+
+        // 0.02° is ~2.2 km north-south and ~1.6 km east-west here.
+        let expected_m2 = region.unsigned_area()
+            * 44.27_f64.to_radians().cos()
+            * crate::constants::MEAN_EARTH_RADIUS.to_radians().powi(2);
+        assert_relative_eq!(stats.area_m2, expected_m2, max_relative = 1e-3);
+
+        // A 0.02° square spans 24 3-arcsecond samples, plus the
+        // partial cells along its outline.
+        assert!((24 * 24..=26 * 26).contains(&stats.len()));
+
+        // The summit region is within the whole.
+        let summit = Rect::new(
+            Coord {
+                x: -71.305,
+                y: 44.268,
+            },
+            Coord {
+                x: -71.300,
+                y: 44.273,
+            },
+        )
+        .to_polygon();
+        assert!(region.contains(&summit));
+        let summit_stats = ZonalStats::new(&tiles, &summit).unwrap();
+        assert_relative_eq!(summit_stats.max_m, stats.max_m);
+        assert!(summit_stats.mean_m > stats.mean_m);
+    }
+
+    #[test]
+    fn test_across_tiles() {
+        // Null Island has no tile, so terrain is flat at 0 m.
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let degree_m = crate::constants::MEAN_EARTH_RADIUS.to_radians();
+
+        // A triangle spanning the four tiles meeting at Null Island.
+        let region = polygon![
+            (x: -0.01, y: -0.01),
+            (x: 0.01, y: -0.01),
+            (x: 0.0, y: 0.01),
+        ];
+        let stats = ZonalStats::new(&tiles, &region).unwrap();
+        assert_eq!((stats.min_m, stats.max_m, stats.mean_m), (0.0, 0.0, 0.0));
+        assert_relative_eq!(
+            stats.area_m2,
+            region.unsigned_area() * degree_m * degree_m,
+            max_relative = 1e-4
+        );
+
+        // Shared edge samples are only counted once.
+        let square =
+            Rect::new(Coord { x: -0.01, y: -0.01 }, Coord { x: 0.01, y: 0.01 }).to_polygon();
+        let stats = ZonalStats::new(&tiles, &square).unwrap();
+        assert_eq!(stats.len(), 25 * 25);
+
+        let results = ZonalStats::par_new(&tiles, &[region, square, polygon![]]);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(matches!(results[2], Err(TerrainError::EmptyRegion)));
+    }
+
+    #[test]
+    fn test_across_antimeridian() {
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let degree_m = crate::constants::MEAN_EARTH_RADIUS.to_radians();
+
+        // A 0.02° square centered on the antimeridian, wrapped as
+        // H3 and GeoJSON outlines are.
+        let wrapped = polygon![
+            (x: 179.99, y: -0.01),
+            (x: -179.99, y: -0.01),
+            (x: -179.99, y: 0.01),
+            (x: 179.99, y: 0.01),
+        ];
+        let stats = ZonalStats::new(&tiles, &wrapped).unwrap();
+        assert_relative_eq!(
+            stats.area_m2,
+            0.02 * 0.02 * degree_m * degree_m,
+            max_relative = 1e-4
+        );
+        assert_eq!(stats.len(), 25 * 25);
+
+        // The same square with continuous longitudes.
+        let unwrapped = Rect::new(
+            Coord {
+                x: 179.99,
+                y: -0.01,
+            },
+            Coord { x: 180.01, y: 0.01 },
+        )
+        .to_polygon();
+        assert_eq!(ZonalStats::new(&tiles, &unwrapped).unwrap(), stats);
+    }
+
+    #[cfg(feature = "h3")]
+    #[test]
+    fn test_h3_cells() {
+        use h3o::{LatLng, Resolution};
+
+        let tiles = Tiles::new(crate::three_arcsecond_dir(), TileMode::MemMap).unwrap();
+        let summit = LatLng::new(44.2706, -71.3033)
+            .unwrap()
+            .to_cell(Resolution::Seven);
+        let cells: Vec<_> = summit.grid_disk(1);
+        let results = ZonalStats::par_from_cells(&tiles, &cells);
+        assert_eq!(results.len(), 7);
+
+        let summit_stats = ZonalStats::from_cell(&tiles, summit).unwrap();
+        let idx = cells.iter().position(|cell| *cell == summit).unwrap();
+        assert_eq!(results[idx].as_ref().unwrap(), &summit_stats);
+        // Resolution 7 cells average ~5.2 km².
+        assert!((4.0e6..=6.5e6).contains(&summit_stats.area_m2));
+        for stats in &results {
+            assert!(stats.as_ref().unwrap().max_m <= summit_stats.max_m);
+        }
+    }
+}